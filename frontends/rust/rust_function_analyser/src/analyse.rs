@@ -46,6 +46,9 @@ pub struct CallSite {
     pub src: String,
     #[serde(rename = "Dst")]
     pub dst: String,
+    // Number of arguments passed at the call, not counting a method receiver
+    #[serde(skip)]
+    pub arg_count: usize,
 }
 
 // Major struct for function elements
@@ -90,6 +93,32 @@ pub struct FunctionInfo {
     pub end_line: usize,
     #[serde(rename = "Callsites")]
     pub callsites: Vec<CallSite>,
+    // Unmodified return type used for resolving receiver types, not part of the yaml output
+    #[serde(skip)]
+    pub full_return_type: String,
+    // Whether the function is marked with #[test] or #[bench], not part of the yaml output
+    #[serde(skip)]
+    pub is_test: bool,
+    // Names of the generic type parameters of the function, not part of the yaml output
+    #[serde(skip)]
+    pub generic_params: Vec<String>,
+    // Trait implemented by the impl block of a method, e.g. `Decode` for a method of
+    // `impl Decode for Raw`, not part of the yaml output
+    #[serde(skip)]
+    pub trait_name: Option<String>,
+    // Sum of the cyclomatic complexity of every function reachable from a harness, counting
+    // each function once. Only set for the fuzz_target FunctionInfo of a harness, and not
+    // part of the yaml output.
+    #[serde(skip)]
+    pub reachable_complexity: usize,
+}
+
+impl FunctionInfo {
+    // Stable identifier of a function built from its location and name, used to cross-link
+    // call tree nodes with the function list
+    pub fn id(&self) -> String {
+        format!("{}:{}:{}", self.file, self.start_line, self.name)
+    }
 }
 
 // Helper struct to keep track of important information throughout the analysis
@@ -102,6 +131,12 @@ pub struct FunctionAnalyser {
     pub first_pass_complete: bool,
 }
 
+impl Default for FunctionAnalyser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // Major implementation for the AST visiting and analysing through the syn crate
 impl FunctionAnalyser {
     pub fn new() -> Self {
@@ -118,9 +153,9 @@ impl FunctionAnalyser {
     // Entry method to analyse rust source files and extract functions/methods definition
     pub fn analyse_file(&mut self, file_path: &str) -> std::io::Result<()> {
         // Parse the rust source code and build an AST by the syn crate
-        let file_content = fs::read_to_string(&file_path)?;
+        let file_content = blank_script_header(&fs::read_to_string(file_path)?);
         let syntax = syn::parse_file(&file_content)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+            .map_err(std::io::Error::other)?;
 
         // Analyse and retrieve a list of functions/methods return value and impl for processing
         self.first_pass_complete = false;
@@ -149,9 +184,18 @@ impl FunctionAnalyser {
                 syn::Item::Impl(item_impl) => {
                     if let syn::Type::Path(type_path) = &*item_impl.self_ty {
                         let impl_type = type_path.path.segments.last().unwrap().ident.to_string();
+                        let trait_name = item_impl
+                            .trait_
+                            .as_ref()
+                            .and_then(|(_, path, _)| path.segments.last())
+                            .map(|seg| seg.ident.to_string());
                         for item in &item_impl.items {
                             if let syn::ImplItem::Fn(method) = item {
+                                let first_new = self.functions.len();
                                 self.visit_method(method, file_path, &impl_type);
+                                for function in &mut self.functions[first_new..] {
+                                    function.trait_name = trait_name.clone();
+                                }
                             }
                         }
                     }
@@ -177,14 +221,17 @@ impl FunctionAnalyser {
             }
         } else {
             let visibility = self.get_visibility(&node.vis);
+            let is_test = self.is_test_function(&node.attrs);
             let (start_line, end_line) = self.get_function_lines(&node.block.brace_token);
             self.process_function(
                 &node.sig.ident.to_string(),
+                &node.sig.generics,
                 &node.sig.inputs,
                 &node.sig.output,
                 &node.block.stmts,
                 file,
                 visibility,
+                is_test,
                 start_line,
                 end_line,
             );
@@ -216,14 +263,17 @@ impl FunctionAnalyser {
         } else {
             self.extract_parameter_types(&node.sig.inputs);
             let visibility = self.get_visibility(&node.vis);
+            let is_test = self.is_test_function(&node.attrs);
             let (start_line, end_line) = self.get_function_lines(&node.block.brace_token);
             self.process_function(
                 &method_name,
+                &node.sig.generics,
                 &node.sig.inputs,
                 &node.sig.output,
                 &node.block.stmts,
                 file,
                 visibility,
+                is_test,
                 start_line,
                 end_line,
             );
@@ -232,14 +282,17 @@ impl FunctionAnalyser {
 
     // Internal method to process each functions/methods when going through them in the AST
     // Used by visit_function and visit_method implementation
+    #[allow(clippy::too_many_arguments)]
     fn process_function(
         &mut self,
         name: &str,
+        generics: &syn::Generics,
         inputs: &syn::punctuated::Punctuated<FnArg, syn::token::Comma>,
         output: &ReturnType,
         stmts: &[Stmt],
         file: &str,
         visibility: String,
+        is_test: bool,
         start_line: usize,
         end_line: usize,
     ) {
@@ -260,12 +313,28 @@ impl FunctionAnalyser {
             }
         };
 
+        // Keep the unmodified return type with Self replaced by the impl type, which is
+        // needed to resolve the receiver type of chained and ?-unwrapped calls
+        let impl_type = name.rfind("::").map(|pos| &name[..pos]);
+        let full_return_type = match output {
+            ReturnType::Default => String::new(),
+            ReturnType::Type(_, ty) => quote::ToTokens::to_token_stream(&**ty)
+                .to_string()
+                .split(' ')
+                .map(|token| match impl_type {
+                    Some(impl_type) if token == "Self" => impl_type,
+                    _ => token,
+                })
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+
         // Discover the arg types Vector of the target function/method
         let arg_types = inputs
             .iter()
             .filter_map(|arg| {
                 if let FnArg::Typed(pat) = arg {
-                    Some(format!("{}", self.clean_function_name(quote::ToTokens::to_token_stream(&*pat.ty).to_string())))
+                    Some(self.clean_function_name(quote::ToTokens::to_token_stream(&*pat.ty).to_string()))
                 } else {
                     None
                 }
@@ -292,7 +361,7 @@ impl FunctionAnalyser {
         let arg_map: HashMap<String, String> = arg_names
             .clone()
             .into_iter()
-            .zip(arg_types.clone().into_iter())
+            .zip(arg_types.clone())
             .collect();
 
         // Calculate the cyclomatic complexity of the target function/method
@@ -351,12 +420,17 @@ impl FunctionAnalyser {
             start_line,
             end_line,
             callsites,
+            full_return_type,
+            is_test,
+            generic_params: generics.type_params().map(|param| param.ident.to_string()).collect(),
+            trait_name: None,
+            reachable_complexity: 0,
         });
 
         self.call_stack
             .entry(name.to_string())
             .or_default()
-            .extend(called_functions.into_iter());
+            .extend(called_functions);
     }
 
     // Internal unboxing method implementation for unwrapping Stmt to Stmt::Expr and call extract_from_expr
@@ -426,6 +500,7 @@ impl FunctionAnalyser {
                     callsites.push(CallSite {
                         src: format!("{},{},{}", file, span.line, span.column),
                         dst: self.clean_function_name(full_path),
+                        arg_count: call_expr.args.len(),
                     });
                 }
 
@@ -475,6 +550,7 @@ impl FunctionAnalyser {
                 callsites.push(CallSite {
                     src: format!("{},{},{}", file, span.line, span.column),
                     dst: self.clean_function_name(full_path),
+                    arg_count: method_call.args.len(),
                 });
 
                 // Handle method/function in arguments
@@ -754,6 +830,16 @@ impl FunctionAnalyser {
         }
     }
 
+    // Check if the attributes mark a test or benchmark function
+    fn is_test_function(&self, attrs: &[syn::Attribute]) -> bool {
+        attrs.iter().any(|attr| {
+            attr.path()
+                .segments
+                .last()
+                .is_some_and(|seg| seg.ident == "test" || seg.ident == "bench")
+        })
+    }
+
     // Internal helper for calculating cyclomatic complexity
     fn calculate_cyclomatic_complexity(&self, stmts: &[Stmt]) -> usize {
         1 + stmts.iter().filter(|stmt| matches!(stmt, Stmt::Expr(..))).count()
@@ -946,15 +1032,13 @@ impl FunctionAnalyser {
 
 
         // Trim unncessary prefix
-        let trimmed_result = if result.starts_with("&mut") {
-            result[4..].to_string()
+        if let Some(stripped) = result.strip_prefix("&mut") {
+            stripped.to_string()
         } else if input.starts_with('&') {
             result[1..].to_string()
         } else {
             result
-        };
-
-        trimmed_result
+        }
     }
 
     // Internal entry method for calculating function depth recursively
@@ -1036,3 +1120,140 @@ pub fn analyse_directory(dir: &str, exclude_dirs: &[&str]) -> std::io::Result<Ve
 
     Ok(analyser.functions)
 }
+
+// Canonicalise a function list whose names are used as unique keys of the function maps.
+// Copies of the same function found at the same location, e.g. by analysing a file twice, are
+// merged into the first copy. Different functions sharing a name, e.g. free functions of the
+// same name in two modules, would silently overwrite each other in the maps instead. The
+// first of them, preferring non-test functions and then the lowest file and line, keeps the
+// plain name and the others are renamed to `<name>@<file>:<line>`. Every duplicate is logged.
+pub fn canonicalize_functions(functions: Vec<FunctionInfo>) -> Vec<FunctionInfo> {
+    let mut canonical: Vec<FunctionInfo> = Vec::new();
+    let mut seen_ids = HashSet::new();
+    for function in functions {
+        if seen_ids.insert(function.id()) {
+            canonical.push(function);
+        } else {
+            eprintln!("Warning: merging duplicate analysis of function {}", function.id());
+        }
+    }
+
+    let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, function) in canonical.iter().enumerate() {
+        groups.entry(function.name.clone()).or_default().push(index);
+    }
+    for mut indices in groups.into_values().filter(|indices| indices.len() > 1) {
+        indices.sort_by_key(|&index| {
+            let function = &canonical[index];
+            (function.is_test, function.file.clone(), function.start_line)
+        });
+        let kept = canonical[indices[0]].id();
+        for &index in &indices[1..] {
+            let function = &mut canonical[index];
+            let renamed = format!("{}@{}:{}", function.name, function.file, function.start_line);
+            eprintln!("Warning: function {} shares its name with {}, renamed to {}", function.id(), kept, renamed);
+            function.name = renamed;
+        }
+    }
+
+    canonical
+}
+
+// Blank out the header of single-file cargo scripts, which is a `#!` line followed by an
+// optional `---` fenced manifest. syn only skips the shebang line itself and fails on the
+// manifest. The lines are blanked instead of removed to keep the line numbers intact.
+pub(crate) fn blank_script_header(content: &str) -> String {
+    let mut lines: Vec<&str> = content.split('\n').collect();
+    let mut index = 0;
+    if lines[0].starts_with("#!") && !lines[0].starts_with("#![") {
+        lines[0] = "";
+        index = 1;
+    }
+    while index < lines.len() && lines[index].trim().is_empty() {
+        index += 1;
+    }
+
+    if let Some(opening) = lines.get(index) {
+        let fence_length = opening.len() - opening.trim_start_matches('-').len();
+        if fence_length >= 3 {
+            let fence = &opening[..fence_length];
+            if let Some(closing) = (index + 1..lines.len()).find(|&line| lines[line].trim_end() == fence) {
+                for line in &mut lines[index..=closing] {
+                    *line = "";
+                }
+            }
+        }
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Analyse a project made of a single library source file
+    fn analyse_source(name: &str, source: &str) -> Vec<FunctionInfo> {
+        let dir = std::env::temp_dir().join(format!("rust_function_analyser-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("lib.rs"), source).unwrap();
+        analyse_directory(dir.to_str().unwrap(), &[]).unwrap()
+    }
+
+    fn function<'a>(functions: &'a [FunctionInfo], name: &str) -> &'a FunctionInfo {
+        functions.iter().find(|function| function.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_and_bench_functions_are_flagged() {
+        let source = "pub fn run() {}
+#[test]
+fn run_works() { run(); }
+#[bench]
+fn run_bench() {}
+";
+        let functions = analyse_source("test_functions", source);
+        assert!(!function(&functions, "run").is_test);
+        assert!(function(&functions, "run_works").is_test);
+        assert!(function(&functions, "run_bench").is_test);
+    }
+
+    #[test]
+    fn cargo_script_headers_are_blanked() {
+        let source = "#!/usr/bin/env cargo
+---
+[dependencies]
+libc = \"0.2\"
+---
+
+pub fn run() {}
+";
+        assert_eq!(blank_script_header(source), "\n\n\n\n\n\npub fn run() {}\n");
+        assert_eq!(blank_script_header("#![allow(unused)]\n---\n"), "#![allow(unused)]\n---\n");
+
+        let functions = analyse_source("cargo_script_header", source);
+        assert_eq!(function(&functions, "run").start_line, 7);
+    }
+
+    #[test]
+    fn duplicate_function_names_are_disambiguated() {
+        let dir = std::env::temp_dir().join(format!("rust_function_analyser-{}-canonicalize", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.rs"), "pub fn helper() {}\n").unwrap();
+        fs::write(dir.join("b.rs"), "\n#[test]\nfn helper() {}\n").unwrap();
+        fs::write(dir.join("c.rs"), "\n\npub fn helper() {}\n").unwrap();
+        let mut functions = analyse_directory(dir.to_str().unwrap(), &[]).unwrap();
+        functions.extend(functions.clone());
+
+        let functions = canonicalize_functions(functions);
+        let mut names: Vec<String> = functions
+            .iter()
+            .map(|function| function.name.replace(&format!("{}/", dir.display()), ""))
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["helper", "helper@b.rs:3", "helper@c.rs:3"]);
+        assert!(functions.iter().any(|function| function.name == "helper" && function.file.ends_with("a.rs")));
+    }
+}
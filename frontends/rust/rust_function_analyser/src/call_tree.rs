@@ -13,506 +13,5012 @@
  * limitations under the License.
  */
 
-use crate::analyse::{CallSite, FunctionInfo};
+use crate::analyse::{blank_script_header, CallSite, FunctionInfo};
+use crate::config::CallTreeConfig;
+use crate::reachability::{estimate_call_depths, ReachabilityBitsets};
+use crate::types::{
+    deref_smart_pointers, generic_arguments, index_element_type, is_iterator_type, iterator_item_type,
+    parse_type, tuple_element_type, type_name, type_to_string, unwrap_try,
+};
 
+use proc_macro2::{Delimiter, LineColumn, Span, TokenStream, TokenTree};
 use syn::{
-    spanned::Spanned, visit::Visit, Expr, ExprCall, ExprMethodCall, ExprPath, Macro, Stmt, Path as SynPath
+    parse::ParseStream, punctuated::Punctuated, spanned::Spanned, visit::Visit, Attribute, Block, Expr,
+    ExprCall, ExprClosure, ExprMethodCall, ExprPath, Macro, QSelf, Stmt, Path as SynPath, Token
 };
 
+use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashSet, HashMap};
 use std::fs::{self, File};
-use std::io::{self, Write};
-use std::path::Path;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Standard wrapper functions that only pass their argument through, by the module defining
+// them, which are not recorded as calls while the calls inside their arguments are still visited
+const TRANSPARENT_WRAPPERS: &[(&str, &str)] = &[("hint", "black_box"), ("mem", "drop"), ("convert", "identity")];
+
+// Standard methods returning a value of the same type as their receiver, which is kept as
+// the result type unless the project defines the method itself
+const IDENTITY_METHODS: &[&str] = &["clone", "to_owned", "cloned", "copied"];
+
+// Standard methods unwrapping a Result or Option receiver like the `?` operator
+const UNWRAP_METHODS: &[&str] = &["unwrap", "expect", "unwrap_or_default", "unwrap_unchecked"];
+
+// Methods converting an Option or Result receiver into a Result of the same value, commonly
+// used right before the `?` operator, including the anyhow and eyre context extensions
+const TRY_CONVERSION_METHODS: &[&str] = &["ok_or", "ok_or_else", "map_err", "context", "with_context"];
+
+// Option and Result methods keeping the wrapper and the value of their receiver, e.g. the
+// filter in `build(d).ok().filter(Parser::is_valid)?`
+const TRY_PRESERVING_METHODS: &[&str] = &["filter", "or", "or_else", "xor", "inspect", "inspect_err", "take"];
+
+// Number of hashes per name in the bloom filter of a capacity bounded visited set
+const BLOOM_HASHES: usize = 3;
+
+// Associated functions conventionally constructing a value of their own type, including the
+// From conversion commonly used to re-bind the fuzz input, e.g. `let data = Input::from(data)`
+const CONSTRUCTOR_NAMES: &[&str] = &["new", "default", "from"];
+
+// Associated functions conventionally constructing a Result of their own type
+const FALLIBLE_CONSTRUCTOR_NAMES: &[&str] = &["try_from", "from_str"];
+
+// Standard collection methods giving an optional reference to an element, e.g. the value of
+// a map from get or the first element of a vector
+const ELEMENT_ACCESS_METHODS: &[&str] = &["get", "get_mut", "first", "last", "first_mut", "last_mut"];
+
+// Methods of interior mutability types giving access to the wrapped value, with the type
+// they produce for the wrapped type $T. Lock guards are wrapped in a Result to be unwrapped.
+const INTERIOR_ACCESS_METHODS: &[(&str, &str, &str)] = &[
+    ("RefCell", "borrow", "& $T"),
+    ("RefCell", "borrow_mut", "& $T"),
+    ("Mutex", "lock", "Result < $T >"),
+    ("RwLock", "read", "Result < $T >"),
+    ("RwLock", "write", "Result < $T >"),
+];
+
+// Functions of std::mem returning the value moved out of the place their first argument
+// mutably borrows
+const MEM_MOVE_FUNCTIONS: &[&str] = &["take", "replace"];
+
+// Standard wrapper types whose `new` takes the wrapped value as its only argument
+const WRAPPER_TYPES: &[&str] = &["Box", "Rc", "Arc", "RefCell", "Mutex", "RwLock"];
+
+// Standard reference conversion methods from AsRef, AsMut, Borrow and BorrowMut, giving a
+// reference to their receiver, or to the value inside an Option or Result receiver
+const REFERENCE_METHODS: &[&str] = &["as_ref", "as_mut", "borrow", "borrow_mut"];
+
+// Standard methods creating an iterator over the elements of a collection
+const ITERATOR_METHODS: &[&str] = &["iter", "iter_mut", "into_iter", "drain", "values", "values_mut"];
+
+// Iterator adapters yielding the items of their receiver unchanged
+const ITERATOR_ADAPTER_METHODS: &[&str] = &[
+    "rev", "skip", "take", "step_by", "filter", "skip_while", "take_while", "peekable", "fuse", "inspect", "chain",
+    "cycle", "by_ref",
+];
+
+// Iterator methods giving an optional single item of their receiver, e.g. the first item
+// from `into_iter().next()`
+const ITERATOR_CONSUMER_METHODS: &[&str] = &[
+    "next", "next_back", "last", "nth", "nth_back", "find", "max", "min", "max_by", "min_by", "max_by_key",
+    "min_by_key", "reduce",
+];
+
+// Indentation unit per call depth in the .data output expected by the LLVM format parser
+const DEFAULT_INDENT: &str = "  ";
+
+// Bounds used for reserving the .data output buffer of a harness
+const MAX_ESTIMATED_DEPTH: usize = 256;
+const ESTIMATED_LINE_LENGTH: usize = 96;
+const MAX_RESERVED_BYTES: usize = 16 << 20;
+
+// Output file of the reachability bitsets of all harnesses
+const REACHABILITY_BITSETS_FILE: &str = "fuzzerReachability.json";
+
+// Functions and methods running a closure or async block argument as a separate task
+const SPAWN_FUNCTIONS: &[&str] = &["spawn", "spawn_blocking", "spawn_local", "scope"];
+
+// Keywords followed by parentheses which are not calls when scanning macro tokens
+const SCAN_KEYWORDS: &[&str] = &["if", "while", "match", "return", "in", "as", "let", "fn", "move"];
 
 pub fn generate_call_trees(
     source_dir: &str,
     functions: &[FunctionInfo],
+    config: &CallTreeConfig,
 ) -> io::Result<HashMap<String, FunctionInfo>> {
-    // Retrieve a list of all fuzzing harnesses
-    let fuzzing_files = find_fuzzing_harnesses(source_dir)?;
-    let function_map: HashMap<String, &FunctionInfo> = functions.iter().map(|f| (f.name.clone(), f)).collect();
+    generate_call_trees_for_dirs(&[source_dir], functions, config)
+}
+
+// Generate the call trees of the harnesses found in any of the given source directories, e.g.
+// fuzz/fuzz_targets and examples, or the members of a workspace. A harness found through
+// more than one directory is only processed once.
+pub fn generate_call_trees_for_dirs(
+    source_dirs: &[&str],
+    functions: &[FunctionInfo],
+    config: &CallTreeConfig,
+) -> io::Result<HashMap<String, FunctionInfo>> {
+    // Retrieve a list of all fuzzing harnesses, or discover them while generating
+    let discovered = discover_unique_harnesses(source_dirs, config);
+    let generator = HarnessGenerator::new(functions, config);
+    let mut call_tree_data = String::new();
+    let generated: Box<dyn Iterator<Item = io::Result<(String, Option<GeneratedHarness>)>>> =
+        match config.worker_threads {
+            Some(threads) => {
+                let harnesses = limit_harnesses(discovered.collect::<io::Result<Vec<_>>>()?, config);
+                let results = generator.generate_concurrently(&harnesses, threads);
+                Box::new(harnesses.into_iter().zip(results).map(|(fuzz_file, result)| Ok((fuzz_file, result?))))
+            }
+            None => {
+                let fuzzing_files: Box<dyn Iterator<Item = io::Result<String>>> =
+                    if config.stream_discovery && config.max_harnesses.is_none() {
+                        Box::new(discovered)
+                    } else {
+                        let harnesses = limit_harnesses(discovered.collect::<io::Result<Vec<_>>>()?, config);
+                        Box::new(harnesses.into_iter().map(Ok))
+                    };
+                Box::new(fuzzing_files.map(|fuzz_file| {
+                    let fuzz_file = fuzz_file?;
+                    let result = generator.generate(&fuzz_file, &mut call_tree_data)?;
+                    Ok((fuzz_file, result))
+                }))
+            }
+        };
 
     let mut harness_map = HashMap::new();
+    let mut unresolved_calls = Vec::new();
+    for generated in generated {
+        if let (fuzz_file, Some(harness)) = generated? {
+            let stats = &harness.stats;
+            if config.log_timings {
+                eprintln!(
+                    "Timings for {}: parse {:?}, walk {:?}, build {:?}, visited set {} bytes, call tree {} bytes",
+                    fuzz_file, stats.parse_time, stats.walk_time, stats.build_time, stats.visited_size, stats.output_size
+                );
+            }
+            unresolved_calls.extend(harness.unresolved_calls);
+            harness_map.insert(fuzz_file, harness.function_info);
+        }
+    }
 
-    // Generate call graph per harness
-    for fuzz_file in &fuzzing_files {
-        let harness_name = Path::new(&fuzz_file)
-            .file_stem()
-            .unwrap()
-            .to_string_lossy()
-            .replace('_', "-");
+    // Optionally output the reachability of all harnesses as bitsets for fast joins
+    if config.emit_reachability_bitsets {
+        let bitsets = ReachabilityBitsets::build(functions, &harness_map);
+        fs::write(REACHABILITY_BITSETS_FILE, serde_json::to_string(&bitsets).map_err(io::Error::other)?)?;
+    }
+
+    if !unresolved_calls.is_empty() {
+        return Err(io::Error::other(format!(
+            "unresolved project calls: {}",
+            unresolved_calls.join(", ")
+        )));
+    }
+
+    Ok(harness_map)
+}
+
+// Call tree of a harness whose output files have been written, with the project calls left
+// unresolved when strict resolution is configured
+struct GeneratedHarness {
+    function_info: FunctionInfo,
+    unresolved_calls: Vec<String>,
+    stats: HarnessStats,
+}
+
+// Time spent on each phase of the call tree generation of a harness, and the memory held by
+// the visited set of the call tree building and by the .data call tree lines
+#[derive(Debug, Default)]
+struct HarnessStats {
+    parse_time: Duration,
+    walk_time: Duration,
+    build_time: Duration,
+    visited_size: usize,
+    output_size: usize,
+}
+
+// State shared by the call tree generation of all harnesses, which is only read while the
+// output of a harness is generated so that several harnesses can be generated at once
+struct HarnessGenerator<'a> {
+    functions: &'a [FunctionInfo],
+    function_map: HashMap<String, &'a FunctionInfo>,
+    call_depths: HashMap<String, usize>,
+    project_types: HashSet<&'a str>,
+    config: &'a CallTreeConfig,
+}
+
+impl<'a> HarnessGenerator<'a> {
+    fn new(functions: &'a [FunctionInfo], config: &'a CallTreeConfig) -> Self {
+        HarnessGenerator {
+            functions,
+            function_map: functions.iter().map(|f| (f.name.clone(), f)).collect(),
+            call_depths: estimate_call_depths(functions),
+            project_types: project_type_names(functions),
+            config,
+        }
+    }
+
+    // Generate the call trees of the harnesses on at most `threads` worker threads, which take
+    // the next harness as soon as they are done with one. The results are in harness order.
+    fn generate_concurrently(&self, harnesses: &[String], threads: usize) -> Vec<io::Result<Option<GeneratedHarness>>> {
+        let next_harness = AtomicUsize::new(0);
+        let mut results: Vec<(usize, io::Result<Option<GeneratedHarness>>)> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads.clamp(1, harnesses.len().max(1)))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut call_tree_data = String::new();
+                        let mut results = Vec::new();
+                        loop {
+                            let index = next_harness.fetch_add(1, Ordering::Relaxed);
+                            let fuzz_file = match harnesses.get(index) {
+                                Some(fuzz_file) => fuzz_file,
+                                None => break results,
+                            };
+                            results.push((index, self.generate(fuzz_file, &mut call_tree_data)));
+                        }
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+                .collect()
+        });
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    // Write the .data lines of the call trees into the reused output buffer. The buffer is
+    // reserved from the number of lines of each tree at half its estimated depth, as the lines
+    // are spread over its levels, and never by more than MAX_RESERVED_BYTES at once.
+    fn write_call_tree_buffer(&self, roots: &[CallTreeNode], call_tree_data: &mut String) {
+        let indent_width = self.config.indent.as_deref().unwrap_or(DEFAULT_INDENT).len();
+        let estimated_size: usize = roots
+            .iter()
+            .map(|root| {
+                let depth = self.call_depths.get(&root.name).copied().unwrap_or(1).min(MAX_ESTIMATED_DEPTH);
+                count_call_tree_lines(root) * (depth.div_ceil(2) * indent_width + ESTIMATED_LINE_LENGTH)
+            })
+            .sum();
+        call_tree_data.clear();
+        call_tree_data.reserve(estimated_size.min(MAX_RESERVED_BYTES));
+        for root in roots {
+            write_call_tree_data(root, 0, self.config, call_tree_data);
+        }
+    }
+
+    // Generate the call tree and output files of a harness, reusing the given output buffer.
+    // A harness which cannot be read or parsed is skipped with a warning.
+    fn generate(&self, fuzz_file: &str, call_tree_data: &mut String) -> io::Result<Option<GeneratedHarness>> {
+        catch_harness_panic(fuzz_file, self.config, || self.generate_harness(fuzz_file, call_tree_data))
+    }
+
+    fn generate_harness(&self, fuzz_file: &str, call_tree_data: &mut String) -> io::Result<Option<GeneratedHarness>> {
+        let config = self.config;
+        let harness_name = harness_name(fuzz_file);
+
+        // Extract functions from the fuzz_target macro in the harness
+        let Some(harness_calls) = extract_or_skip(fuzz_file, self.functions, config)? else {
+            return Ok(None);
+        };
 
         // Prepare initials
         let output_file = format!("fuzzerLogFile-{}.data", harness_name);
         let mut output = File::create(&output_file)?;
 
+        // Use the line of the fuzz_target macro for the header when known
+        let entry_line = harness_calls.entry_line.unwrap_or(-1);
         writeln!(output, "Call tree")?;
-        writeln!(output, "fuzz_target {} linenumber=-1", fuzz_file)?;
-
-        // Extract functions from the fuzz_target macro in the harness
-        let called_functions = extract_called_functions(fuzz_file, functions)?;
+        writeln!(output, "fuzz_target {} linenumber={}", fuzz_file, entry_line)?;
 
         // Build the call tree
-        let mut visited = HashSet::new();
-        for (func_name, line_number) in &called_functions {
-            if let Some(call_tree) = build_call_tree(
-                &func_name,
-                &function_map,
-                fuzz_file,
-                *line_number as i32,
-                &mut visited,
-                0,
-            ) {
-                output.write_all(call_tree.as_bytes())?;
-            }
-        }
-
-        // Manually populate all fields for FunctionInfo
-        let function_info = FunctionInfo {
-            name: "fuzz_target".to_string(),
-            file: fuzz_file.clone(),
-            return_type: String::new(),
-            linkage_type: String::new(),
-            arg_count: 0,
-            arg_names: Vec::new(),
-            arg_types: Vec::new(),
-            constants_touched: Vec::new(),
-            called_functions: called_functions.iter().map(|(name, _)| name.clone()).collect(),
-            branch_profiles: Vec::new(),
-            callsites: called_functions
-                .iter()
-                .map(|(src, _)| CallSite {
-                    src: fuzz_file.clone(),
-                    dst: src.clone(),
-                })
-                .collect(),
-            depth: 0,
-            visibility: String::new(),
-            icount: 0,
-            bbcount: 0,
-            edge_count: 0,
-            complexity: 0,
-            function_uses: 0,
-            start_line: 0,
-            end_line: 0,
+        let build_start = Instant::now();
+        let mut visited = VisitedSet::new(config.visited_capacity);
+        let roots = build_harness_call_trees(fuzz_file, &harness_calls, &self.function_map, &mut visited, config);
+        let build_time = build_start.elapsed();
+
+        self.write_call_tree_buffer(&roots, call_tree_data);
+        output.write_all(call_tree_data.as_bytes())?;
+
+        let stats = HarnessStats {
+            parse_time: harness_calls.parse_time,
+            walk_time: harness_calls.walk_time,
+            build_time,
+            visited_size: visited.memory_size(),
+            output_size: call_tree_data.len(),
         };
-        harness_map.insert(fuzz_file.clone(), function_info);
+
+        // Optionally output the call tree as a GraphViz DOT graph
+        if config.emit_dot {
+            let dot_file = format!("fuzzerLogFile-{}.dot", harness_name);
+            fs::write(dot_file, call_tree_to_dot(&harness_name, &roots, config))?;
+        }
+
+        // Optionally output the call tree as newline delimited JSON
+        if config.emit_ndjson {
+            let ndjson_file = format!("fuzzerLogFile-{}.ndjson", harness_name);
+            fs::write(ndjson_file, call_tree_to_ndjson(&roots, config))?;
+        }
+
+        // Optionally output the call tree as a browsable HTML page
+        if config.emit_html {
+            let html_file = format!("fuzzerLogFile-{}.html", harness_name);
+            fs::write(html_file, call_tree_to_html(&harness_name, &roots, config))?;
+        }
+
+        let mut unresolved_calls = Vec::new();
+        if config.strict_resolution {
+            for root in &roots {
+                collect_unresolved_project_calls(root, &self.project_types, &mut unresolved_calls);
+            }
+        }
+
+        Ok(Some(GeneratedHarness {
+            function_info: build_harness_function_info(fuzz_file, &harness_calls, &roots),
+            unresolved_calls,
+            stats,
+        }))
+    }
+}
+
+// Collect the type names qualifying the project methods, e.g. `Parser` for `Parser::decode`
+fn project_type_names(functions: &[FunctionInfo]) -> HashSet<&str> {
+    functions
+        .iter()
+        .filter_map(|function| function.name.rsplit_once("::"))
+        .map(|(qualifier, _)| qualifier.rsplit("::").next().unwrap_or(qualifier))
+        .collect()
+}
+
+// Collect the unresolved calls of a call tree which look like project calls, described as
+// `name at path:line`. A call looks like a project call when its path starts with crate,
+// self or super, or when it is qualified by a project type.
+fn collect_unresolved_project_calls(node: &CallTreeNode, project_types: &HashSet<&str>, result: &mut Vec<String>) {
+    if node.function.is_none() {
+        let segments: Vec<&str> = node.name.split("::").collect();
+        let project_path = matches!(segments[0], "crate" | "self" | "super");
+        let project_type = segments.len() >= 2 && project_types.contains(segments[segments.len() - 2]);
+        if project_path || project_type {
+            result.push(format!("{} at {}:{}", node.name, node.call_path, node.line_number));
+        }
+    }
+
+    for child in &node.children {
+        collect_unresolved_project_calls(child, project_types, result);
+    }
+}
+
+// Run the analysis of a harness, turning a panic raised by it into a skipped harness with a
+// warning when panics are caught. The default panic hook has already reported the location.
+fn catch_harness_panic<T>(
+    fuzz_file: &str,
+    config: &CallTreeConfig,
+    analyse: impl FnOnce() -> io::Result<Option<T>>,
+) -> io::Result<Option<T>> {
+    if !config.catch_panics {
+        return analyse();
+    }
+    panic::catch_unwind(AssertUnwindSafe(analyse)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown panic");
+        eprintln!("Warning: skipping harness {} whose analysis panicked: {}", fuzz_file, message);
+        Ok(None)
+    })
+}
+
+// Name of a harness as used for its output files, e.g. `fuzz-parser` for fuzz_parser.rs
+fn harness_name(fuzz_file: &str) -> String {
+    Path::new(fuzz_file)
+        .file_stem()
+        .unwrap()
+        .to_string_lossy()
+        .replace('_', "-")
+}
+
+// Build the synthetic fuzz_target FunctionInfo of every fuzzing harness in the source
+// directory without generating any call tree output files
+pub fn build_harness_function_infos(
+    source_dir: &str,
+    functions: &[FunctionInfo],
+    config: &CallTreeConfig,
+) -> io::Result<HashMap<String, FunctionInfo>> {
+    let function_map: HashMap<String, &FunctionInfo> = functions.iter().map(|f| (f.name.clone(), f)).collect();
+    let mut harness_map = HashMap::new();
+
+    let fuzzing_files = find_fuzzing_harnesses(source_dir, &entry_macro_names(config))?;
+    for fuzz_file in limit_harnesses(fuzzing_files, config) {
+        let function_info = catch_harness_panic(&fuzz_file, config, || {
+            let Some(harness_calls) = extract_or_skip(&fuzz_file, functions, config)? else {
+                return Ok(None);
+            };
+            let mut visited = VisitedSet::new(config.visited_capacity);
+            let roots = build_harness_call_trees(&fuzz_file, &harness_calls, &function_map, &mut visited, config);
+            Ok(Some(build_harness_function_info(&fuzz_file, &harness_calls, &roots)))
+        })?;
+        if let Some(function_info) = function_info {
+            harness_map.insert(fuzz_file, function_info);
+        }
     }
 
     Ok(harness_map)
 }
 
-// Locate all fuzzing harness files with fuzz_target macro
-fn find_fuzzing_harnesses(dir: &str) -> io::Result<Vec<String>> {
-    let mut harnesses = Vec::new();
-    for entry in fs::read_dir(dir)? {
-        let path = entry?.path();
-        if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("rs") {
-            let content = fs::read_to_string(&path)?;
-            if content.contains("fuzz_target!") {
-                harnesses.push(path.to_string_lossy().into_owned());
+// Build the call trees rooted at each function called in the fuzz_target macro of a harness.
+// Calls with rejected resolutions are kept as unresolved nodes, and calls made in the
+// arguments of another call are nested under it when argument parents are given.
+fn build_harness_call_trees<'a>(
+    fuzz_file: &str,
+    harness_calls: &HarnessCalls,
+    function_map: &HashMap<String, &'a FunctionInfo>,
+    visited: &mut VisitedSet,
+    config: &CallTreeConfig,
+) -> Vec<CallTreeNode<'a>> {
+    let mut nodes = Vec::new();
+    for item in &harness_calls.called_functions {
+        let (func_name, line_number) = item;
+        let mut node = if harness_calls.arity_mismatches.contains(item) {
+            build_external_node(func_name, fuzz_file, *line_number, config)
+        } else if matches_configured_name(func_name, &config.root_blocklist) {
+            Some(build_blocked_root_node(func_name, function_map, fuzz_file, *line_number))
+        } else {
+            build_call_tree(func_name, function_map, fuzz_file, *line_number, visited, 0, config)
+        };
+        if let Some(node) = node.as_mut() {
+            node.arg_count = harness_calls.arg_counts.get(item).copied();
+            node.written_name = harness_calls.written_names.get(item).cloned();
+        }
+        nodes.push(node);
+    }
+
+    // Attach argument calls to their enclosing call, going backwards so that nested argument
+    // calls are complete before their parent is attached itself. A call stays a root when its
+    // parent has no node of its own, e.g. a revisit which is left out of the .data output.
+    let indices: HashMap<&(String, i32), usize> =
+        harness_calls.called_functions.iter().enumerate().map(|(index, item)| (item, index)).collect();
+    let mut is_root = vec![true; nodes.len()];
+    for index in (0..nodes.len()).rev() {
+        let parent_index = match harness_calls
+            .argument_parents
+            .get(&harness_calls.called_functions[index])
+            .and_then(|parent| indices.get(parent))
+        {
+            Some(parent_index) if *parent_index != index => *parent_index,
+            _ => continue,
+        };
+        if !matches!(&nodes[parent_index], Some(parent) if !parent.revisit) {
+            continue;
+        }
+        if let Some(node) = nodes[index].take() {
+            nodes[parent_index].as_mut().unwrap().children.insert(0, node);
+        }
+        is_root[index] = false;
+    }
+
+    nodes
+        .into_iter()
+        .zip(is_root)
+        .filter_map(|(node, is_root)| node.filter(|_| is_root))
+        .collect()
+}
+
+// Manually populate all fields for the synthetic fuzz_target FunctionInfo of a harness.
+// The depth is the maximum depth reached by the call trees of the harness, and the arguments
+// are the parameters of the harness closure.
+fn build_harness_function_info(
+    fuzz_file: &str,
+    harness_calls: &HarnessCalls,
+    roots: &[CallTreeNode],
+) -> FunctionInfo {
+    let called_functions = &harness_calls.called_functions;
+    let signature = harness_calls.entry_signature.as_ref();
+
+    // The reached functions are unique and sorted by byte order, matching the std::set of
+    // names the LLVM frontend copies into functionsReached
+    let mut reached: Vec<String> = called_functions.iter().map(|(name, _)| name.clone()).collect();
+    reached.sort();
+    reached.dedup();
+
+    FunctionInfo {
+        name: "fuzz_target".to_string(),
+        file: fuzz_file.to_string(),
+        return_type: String::new(),
+        linkage_type: String::new(),
+        arg_count: signature.map_or(0, |signature| signature.arg_types.len()),
+        arg_names: signature.map(|signature| signature.arg_names.clone()).unwrap_or_default(),
+        arg_types: signature.map(|signature| signature.arg_types.clone()).unwrap_or_default(),
+        constants_touched: Vec::new(),
+        called_functions: reached,
+        branch_profiles: Vec::new(),
+        callsites: called_functions
+            .iter()
+            .map(|item| CallSite {
+                src: fuzz_file.to_string(),
+                dst: item.0.clone(),
+                arg_count: harness_calls.arg_counts.get(item).copied().unwrap_or_default(),
+            })
+            .collect(),
+        depth: roots.iter().map(call_tree_depth).max().unwrap_or(0),
+        visibility: String::new(),
+        icount: 0,
+        bbcount: 0,
+        edge_count: 0,
+        complexity: 0,
+        function_uses: 0,
+        start_line: 0,
+        end_line: 0,
+        full_return_type: String::new(),
+        is_test: false,
+        generic_params: Vec::new(),
+        trait_name: None,
+        reachable_complexity: roots.iter().map(reachable_complexity).sum(),
+    }
+}
+
+// Names of the macros marking a fuzzing harness, fuzz_target and the configured entry macros
+fn entry_macro_names(config: &CallTreeConfig) -> Vec<&str> {
+    let mut names = vec!["fuzz_target"];
+    names.extend(config.entry_macros.iter().map(String::as_str));
+    names
+}
+
+// Locate all fuzzing harness files with fuzz_target or another entry macro. A path to a single
+// file is checked on its own instead of being walked.
+fn find_fuzzing_harnesses(dir: &str, entry_macros: &[&str]) -> io::Result<Vec<String>> {
+    HarnessDiscovery::new(dir, entry_macros).collect()
+}
+
+// Lazily locate the fuzzing harness files with fuzz_target or another configured entry macro,
+// yielding each harness as soon as it is found so that its call tree can be generated while
+// the rest of the directory is still being scanned. Harnesses are yielded in the same order
+// as find_fuzzing_harnesses collects them. An error is yielded for the entry it concerns, and
+// the scan goes on with the next entry.
+pub fn discover_harnesses_iter(dir: &str, config: &CallTreeConfig) -> impl Iterator<Item = io::Result<String>> {
+    HarnessDiscovery::new(dir, &entry_macro_names(config))
+}
+
+// Keep only the first harnesses by path when their number is limited in the config, so a
+// partial run always selects the same harnesses
+fn limit_harnesses(mut harnesses: Vec<String>, config: &CallTreeConfig) -> Vec<String> {
+    if let Some(max_harnesses) = config.max_harnesses {
+        harnesses.sort();
+        harnesses.truncate(max_harnesses);
+    }
+    harnesses
+}
+
+// Discover the harnesses of several source directories in order, skipping any harness whose
+// canonical path was already found through an earlier or overlapping directory
+fn discover_unique_harnesses<'a>(
+    source_dirs: &'a [&str],
+    config: &CallTreeConfig,
+) -> impl Iterator<Item = io::Result<String>> + 'a {
+    let entry_macros: Vec<String> = entry_macro_names(config).iter().map(|name| name.to_string()).collect();
+    let mut seen = HashSet::new();
+    source_dirs
+        .iter()
+        .flat_map(move |dir| {
+            let entry_macros: Vec<&str> = entry_macros.iter().map(String::as_str).collect();
+            HarnessDiscovery::new(dir, &entry_macros)
+        })
+        .filter(move |harness| match harness {
+            Ok(path) => seen.insert(fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path))),
+            Err(_) => true,
+        })
+}
+
+// Base struct for the depth first harness discovery, keeping the open directory listings of
+// the current path from the root
+struct HarnessDiscovery {
+    entry_macros: Vec<String>,
+    single_file: Option<PathBuf>,
+    pending_error: Option<io::Error>,
+    listings: Vec<fs::ReadDir>,
+}
+
+impl HarnessDiscovery {
+    fn new(dir: &str, entry_macros: &[&str]) -> Self {
+        let mut discovery = HarnessDiscovery {
+            entry_macros: entry_macros.iter().map(|name| name.to_string()).collect(),
+            single_file: None,
+            pending_error: None,
+            listings: Vec::new(),
+        };
+        if Path::new(dir).is_file() {
+            discovery.single_file = Some(PathBuf::from(dir));
+        } else {
+            match fs::read_dir(dir) {
+                Ok(listing) => discovery.listings.push(listing),
+                Err(err) => discovery.pending_error = Some(err),
+            }
+        }
+        discovery
+    }
+
+    // Check a source file for an entry macro, giving its path if it is a harness
+    fn check_file(&self, path: &Path) -> Option<io::Result<String>> {
+        let entry_macros: Vec<&str> = self.entry_macros.iter().map(String::as_str).collect();
+        match contains_entry_macro(path, &entry_macros) {
+            Ok(true) => Some(Ok(path.to_string_lossy().into_owned())),
+            Ok(false) => None,
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+impl Iterator for HarnessDiscovery {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(err) = self.pending_error.take() {
+            return Some(Err(err));
+        }
+        if let Some(file) = self.single_file.take() {
+            return self.check_file(&file);
+        }
+
+        while let Some(listing) = self.listings.last_mut() {
+            let path = match listing.next() {
+                Some(Ok(entry)) => entry.path(),
+                Some(Err(err)) => return Some(Err(err)),
+                None => {
+                    self.listings.pop();
+                    continue;
+                }
+            };
+            if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("rs") {
+                if let Some(harness) = self.check_file(&path) {
+                    return Some(harness);
+                }
+            } else if path.is_dir() {
+                match fs::read_dir(&path) {
+                    Ok(listing) => self.listings.push(listing),
+                    Err(err) => return Some(Err(err)),
+                }
             }
-        } else if path.is_dir() {
-            harnesses.extend(find_fuzzing_harnesses(path.to_str().unwrap())?);
+        }
+        None
+    }
+}
+
+// Check if the directory contains at least one fuzzing harness with fuzz_target or another
+// configured entry macro, stopping at the first one found, or if a single file path is a
+// harness. Unreadable files and directories are skipped.
+pub fn has_fuzz_harness(dir: &str, config: &CallTreeConfig) -> bool {
+    discover_harnesses_iter(dir, config).any(|harness| harness.is_ok())
+}
+
+// Scan a source file line by line for an invocation of one of the entry macros, stopping at
+// the first match
+fn contains_entry_macro(path: &Path, entry_macros: &[&str]) -> io::Result<bool> {
+    let invocations: Vec<String> = entry_macros.iter().map(|name| format!("{}!", name)).collect();
+    let reader = BufReader::new(File::open(path)?);
+    for line in reader.lines() {
+        let line = line?;
+        if invocations.iter().any(|invocation| line.contains(invocation.as_str())) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+// Base struct for the calls extracted from a fuzzing harness
+pub struct HarnessCalls {
+    pub called_functions: Vec<(String, i32)>,
+    pub entry_line: Option<i32>,
+    // Best effort calls found by scanning the tokens of unparsable macro bodies
+    pub heuristic_calls: Vec<(String, i32)>,
+    // Calls whose argument count does not match the resolved function, kept unresolved
+    pub arity_mismatches: HashSet<(String, i32)>,
+    // Enclosing call of each call made in the arguments of another call, only collected when
+    // argument calls are nested or statement roots are used
+    pub argument_parents: HashMap<(String, i32), (String, i32)>,
+    // Calls in the order the visitor recorded them before any processing, only collected when
+    // raw calls are kept
+    pub raw_called_functions: Vec<(String, i32)>,
+    // Number of arguments passed at each call, not counting a method receiver
+    pub arg_counts: HashMap<(String, i32), usize>,
+    // Callee of each call as written in the harness source, before it was resolved
+    pub written_names: HashMap<(String, i32), String>,
+    // Parameters of the harness closure describing the shape of the fuzz input, if the entry
+    // macro body is a closure
+    pub entry_signature: Option<EntrySignature>,
+    // Time spent parsing the harness source and walking its syntax tree
+    pub parse_time: Duration,
+    pub walk_time: Duration,
+}
+
+// Parameters of a harness closure, e.g. `data` of type `&[u8]` for raw bytes or `input` of an
+// Arbitrary type. An untyped parameter has the type `_` and a destructuring pattern the name
+// `_`, so both lists always have one entry per parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntrySignature {
+    pub arg_names: Vec<String>,
+    pub arg_types: Vec<String>,
+}
+
+impl EntrySignature {
+    fn from_closure(closure: &ExprClosure) -> Self {
+        let (arg_names, arg_types) = closure
+            .inputs
+            .iter()
+            .map(|input| {
+                let (pat, ty) = match input {
+                    syn::Pat::Type(pat_type) => (&*pat_type.pat, type_to_string(&pat_type.ty)),
+                    pat => (pat, "_".to_string()),
+                };
+                let name = match pat {
+                    syn::Pat::Ident(ident) => ident.ident.to_string(),
+                    _ => "_".to_string(),
+                };
+                (name, ty)
+            })
+            .unzip();
+        EntrySignature { arg_names, arg_types }
+    }
+}
+
+// Extract the calls of a harness, skipping it with a warning when it cannot be parsed or read
+fn extract_or_skip(
+    fuzz_file: &str,
+    functions: &[FunctionInfo],
+    config: &CallTreeConfig,
+) -> io::Result<Option<HarnessCalls>> {
+    match extract_called_functions(fuzz_file, functions, config) {
+        Ok(harness_calls) => Ok(Some(harness_calls)),
+        Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+            eprintln!("Warning: skipping harness {} which cannot be parsed: {}", fuzz_file, err);
+            Ok(None)
+        }
+        // The file may have been removed or become unreadable since it was discovered
+        Err(err) => {
+            eprintln!("Warning: skipping harness {} which cannot be read: {}", fuzz_file, err);
+            Ok(None)
         }
     }
-    Ok(harnesses)
 }
 
-// Extract all functions in the fuzz_target macro in the fuzzing harnesses
-fn extract_called_functions(
+// Extract all functions in the fuzz_target macro in the fuzzing harnesses. A harness that
+// cannot be parsed is reported as an InvalidData error.
+pub fn extract_called_functions(
     file_path: &str,
     function_info: &[FunctionInfo],
-) -> io::Result<Vec<(String, usize)>> {
-    let content = fs::read_to_string(file_path)?;
-    let syntax = syn::parse_file(&content).expect("Failed to parse file");
+    config: &CallTreeConfig,
+) -> io::Result<HarnessCalls> {
+    let parse_start = Instant::now();
+    let content = match &config.source_expander {
+        Some(expand) => expand(file_path)?,
+        None => blank_script_header(&fs::read_to_string(file_path)?),
+    };
+    let syntax = syn::parse_file(&content).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    let parse_time = parse_start.elapsed();
 
-    let mut visitor = FuzzTargetVisitor::new(function_info.to_vec());
+    let walk_start = Instant::now();
+    let mut visitor = FuzzTargetVisitor::new(function_info, config, file_path);
     visitor.visit_file(&syntax);
+    let raw_called_functions = if config.keep_raw_calls {
+        visitor.called_functions.clone()
+    } else {
+        Vec::new()
+    };
+
+    let mut result: Vec<(String, i32)> = if config.statement_roots && !visitor.statement_roots.is_empty() {
+        // Keep the calls of each statement in statement order, removing duplicates
+        let mut seen = HashSet::new();
+        visitor
+            .statement_roots
+            .into_iter()
+            .filter(|item| seen.insert(item.clone()))
+            .collect()
+    } else {
+        // Remove duplicate items in visiting order and sort by line number. The stable sort keeps
+        // calls of the same line in the order they were visited.
+        let mut seen = HashSet::new();
+        let mut result: Vec<(String, i32)> = visitor
+            .called_functions
+            .into_iter()
+            .filter(|item| seen.insert(item.clone()))
+            .collect();
+        result.sort_by_key(|item| item.1);
+        result
+    };
+    let arities: Vec<Option<(usize, bool)>> =
+        result.iter().map(|item| visitor.call_arities.get(item).copied()).collect();
+    let unprocessed = result.clone();
+    result = post_process_called_functions(result, &arities);
+    let arg_counts: HashMap<(String, i32), usize> = result
+        .iter()
+        .zip(&arities)
+        .filter_map(|(item, arity)| arity.map(|(arg_count, _)| (item.clone(), arg_count)))
+        .collect();
+    let written_names: HashMap<(String, i32), String> = unprocessed
+        .iter()
+        .zip(&result)
+        .filter_map(|(item, processed_item)| Some((processed_item.clone(), visitor.written_names.get(item)?.clone())))
+        .collect();
+
+    // Carry the enclosing calls of argument calls over to the post processed names. Calls
+    // enclosed by a statement root are always nested under it.
+    let mut argument_parents = HashMap::new();
+    if config.nest_argument_calls || config.statement_roots {
+        let processed: HashMap<&(String, i32), &(String, i32)> = unprocessed.iter().zip(result.iter()).collect();
+        for (item, processed_item) in &processed {
+            if let Some(parent) = visitor.call_parents.get(*item).and_then(|parent| processed.get(parent)) {
+                argument_parents.insert((*processed_item).clone(), (*parent).clone());
+            }
+        }
+    }
+
+    // Reject resolutions to functions taking a different number of arguments than the call
+    let mut arity_mismatches = HashSet::new();
+    if config.check_call_arity {
+        for (item, arity) in result.iter().zip(arities) {
+            let (Some((arg_count, method_syntax)), Some(function)) =
+                (arity, find_function(&item.0, &visitor.function_map))
+            else {
+                continue;
+            };
+
+            // Path calls of methods may pass the receiver as the first argument
+            let matches = arg_count == function.arg_count
+                || (!method_syntax && function.name.contains("::") && arg_count == function.arg_count + 1);
+            if !matches {
+                eprintln!(
+                    "Warning: call to {} with {} arguments at {}:{} does not match {} taking {}",
+                    item.0, arg_count, file_path, item.1, function.name, function.arg_count
+                );
+                arity_mismatches.insert(item.clone());
+            }
+        }
+    }
+
+    // Root the call trees at the configured functions instead of the detected calls. A root
+    // keeps the line of its detected call if there is one, or the line of the entry macro.
+    if let Some(roots) = config.root_overrides.get(&harness_name(file_path)) {
+        let entry_line = visitor.entry_line.unwrap_or(-1);
+        result = roots
+            .iter()
+            .map(|root| match result.iter().find(|(name, _)| name == root) {
+                Some(item) => item.clone(),
+                None => (root.clone(), entry_line),
+            })
+            .collect();
+    }
 
-    // Remove duplicate items and sort by line number
-    let set: HashSet<_> = visitor.called_functions.into_iter().collect();
-    let mut result: Vec<(String, usize)> = set.into_iter().collect();
-    result.sort_by_key(|item| item.1);
-    result = post_process_called_functions(result);
+    Ok(HarnessCalls {
+        called_functions: result,
+        entry_line: visitor.entry_line,
+        entry_signature: visitor.entry_signature,
+        heuristic_calls: visitor.heuristic_calls,
+        arity_mismatches,
+        argument_parents,
+        raw_called_functions,
+        arg_counts,
+        written_names,
+        parse_time,
+        walk_time: walk_start.elapsed(),
+    })
+}
+
+// Group the trait implementations of the methods by their name qualified by the implementing
+// type, ignoring the location suffix of canonicalised duplicate names
+fn trait_methods(functions: &[FunctionInfo]) -> HashMap<String, Vec<&FunctionInfo>> {
+    let mut trait_methods: HashMap<String, Vec<&FunctionInfo>> = HashMap::new();
+    for function in functions.iter().filter(|function| function.trait_name.is_some()) {
+        let name = function.name.split('@').next().unwrap_or_default();
+        trait_methods.entry(name.to_string()).or_default().push(function);
+    }
+    trait_methods
+}
 
-    Ok(result)
+// Render the callee of a path call as written in the source, without whitespace
+fn written_name<T: quote::ToTokens>(callee: &T) -> String {
+    callee.to_token_stream().to_string().replace(' ', "")
 }
 
-// Helper function to post process the called function vector
-fn post_process_called_functions(items: Vec<(String, usize)>) -> Vec<(String, usize)> {
+// Helper function to post process the called function vector. Plain function calls such as
+// a harness local `run(data)`, known from their recorded arity, are never qualified.
+fn post_process_called_functions(
+    items: Vec<(String, i32)>,
+    arities: &[Option<(usize, bool)>],
+) -> Vec<(String, i32)> {
     let mut stored_value: Option<String> = None;
     let mut result = Vec::new();
 
-    for (mut string_value, usize_value) in items {
+    for ((mut string_value, line_number), arity) in items.into_iter().zip(arities) {
+        let path_call = matches!(arity, Some((_, false)));
         if let Some(pos) = string_value.rfind("::") {
             stored_value = Some(string_value[..pos].to_string());
-        } else if let Some(stored) = &stored_value {
+        } else if let (Some(stored), false) = (&stored_value, path_call) {
             string_value = format!("{}::{}", stored, string_value);
         }
 
         // Push the updated item into the result
-        result.push((string_value, usize_value));
+        result.push((string_value, line_number));
     }
 
     result
 }
 
 // Base struct and syn:Visit implementation for traversing the function call tree
-#[derive(Default)]
-struct FuzzTargetVisitor {
-    called_functions: Vec<(String, usize)>,
-    function_info: Vec<FunctionInfo>,
+struct FuzzTargetVisitor<'a> {
+    called_functions: Vec<(String, i32)>,
+    function_map: HashMap<String, &'a FunctionInfo>,
     variable_types: HashMap<String, String>,
+    config: &'a CallTreeConfig,
+    macro_line: Option<usize>,
+    entry_line: Option<i32>,
+    entry_signature: Option<EntrySignature>,
+    include_stack: Vec<PathBuf>,
+    // Statement nesting depth relative to the harness body, None outside of a harness macro
+    harness_depth: Option<usize>,
+    // Calls of the current top-level harness statement with the start and end of their span
+    statement_calls: Vec<(LineColumn, LineColumn, String, i32)>,
+    statement_roots: Vec<(String, i32)>,
+    heuristic_calls: Vec<(String, i32)>,
+    // Argument count of each recorded call and whether it uses method call syntax
+    call_arities: HashMap<(String, i32), (usize, bool)>,
+    // Callee of each recorded call as written in the source, e.g. `run` for `p.run()`
+    written_names: HashMap<(String, i32), String>,
+    // Traits brought into scope by use declarations or declared in the harness, and the trait
+    // implementations of each method name qualified by its type, e.g. `Raw::decode`
+    traits_in_scope: HashSet<String>,
+    trait_methods: HashMap<String, Vec<&'a FunctionInfo>>,
+    // Names of the types defining project methods, e.g. `Engine` for `Engine::run`
+    project_types: HashSet<&'a str>,
+    // Type aliases declared in the harness mapped to their target type
+    type_aliases: HashMap<String, String>,
+    // Field types of the structs declared in the harness, by struct and field name
+    struct_fields: HashMap<String, HashMap<String, String>>,
+    // Expansion templates of the trivial macro_rules! macros declared in the harness, in the
+    // format of the config macro patterns
+    local_macros: HashMap<String, String>,
+    // Generic functions declared in the harness, visited again for every turbofish
+    // instantiation, and the ones currently being instantiated
+    generic_helpers: HashMap<String, syn::ItemFn>,
+    instantiating: Vec<String>,
+    // Type parameters of the generic functions being visited outside of an instantiation, e.g.
+    // `T` of `fn drive<T: Run>(d: &[u8])`, whose calls like `T::run` name no concrete type
+    unbound_type_params: Vec<String>,
+    // Non generic functions declared at the top level of the harness, which a harness macro may
+    // name as its body
+    local_functions: HashMap<String, syn::ItemFn>,
+    // Call whose arguments are being visited and the enclosing call of each argument call
+    enclosing_call: Option<(String, i32)>,
+    call_parents: HashMap<(String, i32), (String, i32)>,
 }
 
-impl FuzzTargetVisitor {
-    pub fn new(function_info: Vec<FunctionInfo>) -> Self {
+impl<'a> FuzzTargetVisitor<'a> {
+    pub fn new(function_info: &'a [FunctionInfo], config: &'a CallTreeConfig, file_path: &str) -> Self {
         FuzzTargetVisitor {
             called_functions: Vec::new(),
-            function_info,
+            function_map: function_info.iter().map(|f| (f.name.clone(), f)).collect(),
             variable_types: HashMap::new(),
+            config,
+            macro_line: None,
+            entry_line: None,
+            entry_signature: None,
+            include_stack: vec![PathBuf::from(file_path)],
+            harness_depth: None,
+            statement_calls: Vec::new(),
+            statement_roots: Vec::new(),
+            heuristic_calls: Vec::new(),
+            call_arities: HashMap::new(),
+            written_names: HashMap::new(),
+            traits_in_scope: HashSet::new(),
+            trait_methods: trait_methods(function_info),
+            project_types: project_type_names(function_info),
+            type_aliases: HashMap::new(),
+            struct_fields: HashMap::new(),
+            local_macros: HashMap::new(),
+            generic_helpers: HashMap::new(),
+            instantiating: Vec::new(),
+            unbound_type_params: Vec::new(),
+            local_functions: HashMap::new(),
+            enclosing_call: None,
+            call_parents: HashMap::new(),
         }
     }
 
-    // Helper method to extract type of method call receiver
-    fn extract_receiver_type(&self, receiver: &Expr) -> Option<String> {
-        match receiver {
-            // Variable or parameter call
-            Expr::Path(path_expr) => {
-                let variable_name = path_expr.path.segments.last()?.ident.to_string();
-                self.variable_types.get(&variable_name).cloned()
-            }
-
-            // Chained method call
-            Expr::MethodCall(method_call) => {
-                let receiver_type = self.extract_receiver_type(&method_call.receiver);
-                let method_name = method_call.method.to_string();
-                let name = match receiver_type {
-                    Some(receiver) => format!("{}::{}", receiver, method_name),
-                    None => method_name.clone(),
-                };
-                self.lookup_function_return_type(&name)
+    // Record a called function, also keeping it as a root candidate of the current
+    // top-level statement when inside a harness body. Calls qualified by an unbound type
+    // parameter are left out, as they are recorded by the instantiations of their function.
+    fn record_call(&mut self, name: String, line_number: i32, span: Span) {
+        if let Some((qualifier, _)) = name.split_once("::") {
+            if self.unbound_type_params.iter().any(|param| param == qualifier) {
+                return;
             }
-
-            _ => None,
         }
+        if self.harness_depth.is_some() {
+            self.statement_calls
+                .push((span.start(), span.end(), name.clone(), line_number));
+        }
+        if let Some(parent) = &self.enclosing_call {
+            self.call_parents
+                .entry((name.clone(), line_number))
+                .or_insert_with(|| parent.clone());
+        }
+        self.called_functions.push((name, line_number));
     }
 
-    // Helper method to lookup function return type for reference
-    fn lookup_function_return_type(&self, method_name: &str) -> Option<String> {
-        let function_map: HashMap<String, &FunctionInfo> = self.function_info.iter().map(|f| (f.name.clone(), f)).collect();
+    // Take the calls recorded for the finished top-level statement as roots. The outermost
+    // calls, whose span is not enclosed by another call, e.g. one per match arm, stay roots and
+    // every other call becomes a child of the innermost call enclosing it, e.g. `g` of `f(g())`.
+    fn flush_statement_roots(&mut self) {
+        let mut calls = std::mem::take(&mut self.statement_calls);
+        calls.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
 
-        if let Some(function_info) = find_function(method_name, &function_map) {
-            return Some(function_info.return_type.clone());
+        let mut enclosing: Vec<(LineColumn, (String, i32))> = Vec::new();
+        for (start, end, name, line_number) in calls {
+            while enclosing.last().is_some_and(|(enclosing_end, _)| start >= *enclosing_end) {
+                enclosing.pop();
+            }
+            let call = (name, line_number);
+            if let Some((_, parent)) = enclosing.last() {
+                if *parent != call {
+                    self.call_parents.entry(call.clone()).or_insert_with(|| parent.clone());
+                }
+            }
+            enclosing.push((end, call.clone()));
+            self.statement_roots.push(call);
         }
-        None
     }
 
-    // Try extracting the local variable name creation
-    fn extract_variable_name(&self, pat: &syn::Pat) -> Option<String> {
-        if let syn::Pat::Ident(ident) = pat {
-            Some(ident.ident.to_string())
-        } else {
-            None
-        }
+    // Visit the body of a harness macro with the statement depth reset, so that the
+    // statements of its closure are the top-level statements for root selection
+    fn visit_harness_body(&mut self, visit: impl FnOnce(&mut Self)) {
+        let outer_depth = self.harness_depth.replace(0);
+        let outer_calls = std::mem::take(&mut self.statement_calls);
+        visit(self);
+        // Expression bodies without statements form a single statement
+        self.flush_statement_roots();
+        self.harness_depth = outer_depth;
+        self.statement_calls = outer_calls;
     }
-}
 
-impl<'ast> Visit<'ast> for FuzzTargetVisitor {
-    // visit implementation method for locating the statement in the fuzz_target macro
-    fn visit_macro(&mut self, mac: &'ast Macro) {
-        if mac.path.segments.last().unwrap().ident == "fuzz_target" {
-            if let Ok(body) = mac.parse_body::<Expr>() {
-                self.visit_expr(&body);
-            }
+    // Parse and visit the file referenced by an include! macro in the context of the harness.
+    // The path is resolved relative to the file containing the include! macro and calls found
+    // in the included source are reported at the line of the include! macro.
+    fn visit_include_macro(&mut self, mac: &Macro) -> bool {
+        if mac.path.segments.last().is_none_or(|segment| segment.ident != "include") {
+            return false;
         }
-    }
+        let relative_path = match mac.parse_body::<syn::LitStr>() {
+            Ok(lit) => lit.value(),
+            Err(_) => return false,
+        };
 
-    // visit implementation method for processing each function expression
-    fn visit_expr_call(&mut self, node: &'ast ExprCall) {
-        if let Expr::Path(ExprPath { path, .. }) = &*node.func {
-            let qualified_name = path_to_string(&path);
-            let line_number = node.func.span().start().line;
-            self.called_functions.push((qualified_name, line_number));
+        let current_file = self.include_stack.last().cloned().unwrap_or_default();
+        let include_path = current_file.parent().unwrap_or(Path::new("")).join(relative_path);
+        if self.include_stack.contains(&include_path) {
+            return true;
         }
+        let content = match fs::read_to_string(&include_path) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Warning: Failed to read included file {}: {}", include_path.display(), e);
+                return true;
+            }
+        };
 
-        for arg in &node.args {
-            self.visit_expr(arg);
+        // Included files either contain items or a single expression
+        let include_line = mac.path.span().start().line;
+        let previous_line = self.macro_line.replace(self.macro_line.unwrap_or(include_line));
+        self.include_stack.push(include_path.clone());
+        if let Ok(included_file) = syn::parse_file(&content) {
+            self.visit_file(&included_file);
+        } else if let Ok(included_expr) = syn::parse_str::<Expr>(&content) {
+            self.visit_expr(&included_expr);
+        } else {
+            eprintln!("Warning: Failed to parse included file {}", include_path.display());
         }
+        self.include_stack.pop();
+        self.macro_line = previous_line;
+        true
     }
 
-    // visit implementation method for handling echo method experssion
-    fn visit_expr_method_call(&mut self, node: &'ast ExprMethodCall) {
-        let method_name = node.method.to_string();
-        let span = node.method.span().start();
-        let line_number = span.line;
+    // Helper method to determine the line number to record for a call. Calls synthesized
+    // from a registered macro pattern are reported at the line of the macro invocation.
+    fn call_line(&self, span_line: usize) -> i32 {
+        normalise_line(self.macro_line.unwrap_or(span_line))
+    }
 
-        // Determine the fully qualified name
-        let receiver_type = self.extract_receiver_type(&node.receiver);
-        let qualified_name = match receiver_type {
-            Some(receiver) => format!("{}::{}", receiver, method_name),
-            None => method_name.clone(),
+    // Expand a macro registered in the config macro patterns and visit the resulting
+    // expression. Returns false if the macro is not registered or cannot be expanded.
+    fn visit_registered_macro(&mut self, mac: &Macro) -> bool {
+        let macro_name = match mac.path.segments.last() {
+            Some(segment) => segment.ident.to_string(),
+            None => return false,
+        };
+        let template = match self
+            .config
+            .macro_patterns
+            .get(&macro_name)
+            .or_else(|| self.local_macros.get(&macro_name))
+        {
+            Some(template) => template,
+            None => return false,
         };
 
-        self.called_functions.push((qualified_name, line_number));
-
-        self.visit_expr(&node.receiver);
-        for arg in &node.args {
-            self.visit_expr(arg);
+        // Substitute the macro arguments into the template, starting from the highest
+        // index so that `$1` does not clobber the prefix of `$10`
+        let args = match mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+            Ok(args) => args,
+            Err(_) => return false,
+        };
+        let mut expansion = template.clone();
+        for (index, arg) in args.iter().enumerate().rev() {
+            let arg_source = quote::ToTokens::to_token_stream(arg).to_string();
+            expansion = expansion.replace(&format!("${}", index + 1), &arg_source);
         }
-    }
 
-    // visit implementation for local variables
-    fn visit_local(&mut self, local: &syn::Local) {
-        if let Some(init_expr) = &local.init {
-            if let Some(var_name) = self.extract_variable_name(&local.pat) {
-                if let Some(var_type) = self.extract_receiver_type(&init_expr.expr) {
-                    self.variable_types.insert(var_name, var_type);
-                }
+        match syn::parse_str::<Expr>(&expansion) {
+            Ok(expanded) => {
+                let previous_line = self.macro_line.replace(mac.path.span().start().line);
+                self.visit_expr(&expanded);
+                self.macro_line = previous_line;
+                true
             }
+            Err(_) => false,
         }
     }
 
-    // General method ensure visiting all kinds of Expr that could call functions/methods
-    fn visit_expr(&mut self, expr: &'ast Expr) {
-        match expr {
-            Expr::Call(call_expr) => {
-                self.visit_expr_call(call_expr);
-            }
+    // Helper method to extract type of method call receiver, falling back to the custom
+    // resolvers of the config
+    fn extract_receiver_type(&self, receiver: &Expr) -> Option<String> {
+        self.extract_builtin_receiver_type(receiver)
+            .or_else(|| self.resolve_custom_type(receiver))
+    }
 
-            Expr::MethodCall(method_call_expr) => {
-                self.visit_expr_method_call(method_call_expr);
-            }
+    // Type an expression with the first custom resolver of the config that knows it
+    fn resolve_custom_type(&self, expr: &Expr) -> Option<String> {
+        let type_of = |expr: &Expr| self.extract_receiver_type(expr);
+        self.config
+            .receiver_resolvers
+            .iter()
+            .find_map(|resolver| resolver.resolve_type(expr, &type_of))
+            .map(|ty| parse_type(&ty).map(|parsed| type_to_string(&parsed)).unwrap_or(ty))
+    }
 
-            Expr::Block(block_expr) => {
-                for stmt in &block_expr.block.stmts {
-                    match stmt {
-                        Stmt::Local(local_stmt) => {
-                            if let Some(init_expr) = &local_stmt.init {
-                                self.visit_expr(&init_expr.expr);
-                            }
-                        }
-
-                        Stmt::Expr(inner_expr, _) => {
-                            self.visit_expr(inner_expr);
-                        }
+    fn extract_builtin_receiver_type(&self, receiver: &Expr) -> Option<String> {
+        match receiver {
+            // Variable or parameter call, or the value of a unit struct like `Engine`
+            Expr::Path(path_expr) => {
+                let variable_name = path_expr.path.segments.last()?.ident.to_string();
+                self.variable_types.get(&variable_name).cloned().or_else(|| {
+                    let name = self.expand_path_alias(path_to_string(path_expr.qself.as_ref(), &path_expr.path));
+                    let type_name = type_name(&name);
+                    (self.project_types.contains(type_name.as_str()) || self.struct_fields.contains_key(&name))
+                        .then_some(name)
+                })
+            }
 
-                        Stmt::Item(item) => {
-                            syn::visit::visit_item(self, item);
-                        }
+            // Chained method call
+            Expr::MethodCall(method_call) => {
+                let receiver_type = self
+                    .extract_receiver_type(&method_call.receiver)
+                    .map(|ty| deref_smart_pointers(&self.expand_type_alias(ty)));
+                let method_name = method_call.method.to_string();
+                let name = self.prefer_trait_in_scope(qualify_method(receiver_type.clone(), &method_name));
+                self.lookup_function_return_type(&name, &turbofish_types(method_call.turbofish.as_ref()))
+                    .or_else(|| std_method_return_type(method_call, &method_name, receiver_type))
+            }
 
-                        _ => {}
-                    }
+            // Function or associated function call
+            Expr::Call(call_expr) => {
+                if let Expr::Path(ExprPath { qself, path, .. }) = &*call_expr.func {
+                    let turbofish = match path.segments.last().map(|segment| &segment.arguments) {
+                        Some(syn::PathArguments::AngleBracketed(arguments)) => turbofish_types(Some(arguments)),
+                        _ => Vec::new(),
+                    };
+                    let written_name = path_to_string(qself.as_ref(), path);
+                    // Full target of a type alias qualifying the call, e.g. `Vec<u8>` for
+                    // `Bytes::from` with `type Bytes = Vec<u8>`
+                    let alias_target = written_name
+                        .rsplit_once("::")
+                        .and_then(|(qualifier, _)| self.type_aliases.get(qualifier));
+                    let name = self.expand_path_alias(written_name.clone());
+                    self.lookup_function_return_type(&name, &turbofish)
+                        // Constructor of a tuple struct defined in the harness
+                        .or_else(|| self.struct_fields.contains_key(&name).then(|| name.clone()))
+                        // Conventional constructor of a type outside the project, e.g.
+                        // `Unstructured::new(data)`
+                        .or_else(|| match name.rsplit_once("::") {
+                            Some((qualifier, constructor))
+                                if CONSTRUCTOR_NAMES.contains(&constructor)
+                                    && type_name(qualifier).starts_with(char::is_uppercase) =>
+                            {
+                                // A standard wrapper is generic over the value it wraps
+                                let wrapped = call_expr.args.first().filter(|_| {
+                                    WRAPPER_TYPES.contains(&qualifier) && call_expr.args.len() == 1
+                                });
+                                match wrapped.and_then(|arg| self.extract_receiver_type(arg)) {
+                                    Some(inner) => Some(format!("{} < {} >", qualifier, inner)),
+                                    None => Some(alias_target.map_or(qualifier, String::as_str).to_string()),
+                                }
+                            }
+                            Some((qualifier, constructor))
+                                if FALLIBLE_CONSTRUCTOR_NAMES.contains(&constructor)
+                                    && type_name(qualifier).starts_with(char::is_uppercase) =>
+                            {
+                                Some(format!("Result < {} >", alias_target.map_or(qualifier, String::as_str)))
+                            }
+                            _ => None,
+                        })
+                        // Value moved out of the place borrowed by the first argument, e.g.
+                        // `mem::take(&mut state.parser)`
+                        .or_else(|| match name.rsplit_once("::") {
+                            Some((qualifier, function))
+                                if MEM_MOVE_FUNCTIONS.contains(&function)
+                                    && (qualifier == "mem" || qualifier.ends_with("::mem")) =>
+                            {
+                                self.extract_receiver_type(call_expr.args.first()?)
+                            }
+                            _ => None,
+                        })
+                        // Option or Result variant wrapping a typed value, e.g. `Some(Parser::new())`
+                        .or_else(|| {
+                            let wrapper = match name.as_str() {
+                                "Some" => "Option",
+                                "Ok" => "Result",
+                                _ => return None,
+                            };
+                            let inner = self.extract_receiver_type(call_expr.args.first()?)?;
+                            Some(format!("{} < {} >", wrapper, inner))
+                        })
+                } else {
+                    None
                 }
             }
 
-            Expr::If(if_expr) => {
-                self.visit_expr(&if_expr.cond);
-                self.visit_block(&if_expr.then_branch);
-                if let Some((_, else_branch)) = &if_expr.else_branch {
-                    self.visit_expr(else_branch);
-                }
+            // Unwrapping a Result or Option with the ? operator
+            Expr::Try(try_expr) => {
+                self.extract_receiver_type(&try_expr.expr).map(|ty| unwrap_try(&ty))
             }
 
-            Expr::Match(match_expr) => {
-                self.visit_expr(&match_expr.expr);
-                for arm in &match_expr.arms {
-                    self.visit_expr(&arm.body);
+            Expr::Paren(paren_expr) => self.extract_receiver_type(&paren_expr.expr),
+
+            Expr::Reference(reference_expr) => self.extract_receiver_type(&reference_expr.expr),
+
+            // Struct literal of a harness local or project struct
+            Expr::Struct(struct_expr) => Some(
+                self.expand_path_alias(path_to_string(struct_expr.qself.as_ref(), &struct_expr.path)),
+            ),
+
+            // Element of a tuple or field of a struct defined in the harness
+            Expr::Field(field_expr) => {
+                let base_type = self.extract_receiver_type(&field_expr.base)?;
+                if let syn::Member::Unnamed(index) = &field_expr.member {
+                    if let Some(element_type) = tuple_element_type(&base_type, index.index as usize) {
+                        return Some(element_type);
+                    }
                 }
+                self.struct_fields
+                    .get(&type_name(&base_type))?
+                    .get(&member_name(&field_expr.member))
+                    .cloned()
             }
 
-            Expr::While(while_expr) => {
-                self.visit_expr(&while_expr.cond);
-                self.visit_block(&while_expr.body);
+            // Match whose arms all produce the same type, ignoring arms that never produce a
+            // value such as `return` or `panic!()`
+            Expr::Match(match_expr) => {
+                let mut arm_types = match_expr
+                    .arms
+                    .iter()
+                    .filter(|arm| !matches!(&*arm.body, Expr::Return(_) | Expr::Break(_) | Expr::Continue(_) | Expr::Macro(_)))
+                    .map(|arm| self.extract_receiver_type(&arm.body));
+                let first = arm_types.next()??;
+                let name = type_name(&first);
+                arm_types
+                    .all(|ty| ty.is_some_and(|ty| type_name(&ty) == name))
+                    .then_some(first)
             }
 
-            Expr::ForLoop(for_loop_expr) => {
-                self.visit_expr(&for_loop_expr.expr);
-                self.visit_block(&for_loop_expr.body);
-            }
+            // Block producing the value of its final expression
+            Expr::Block(block_expr) => match block_expr.block.stmts.last() {
+                Some(Stmt::Expr(expr, None)) => self.extract_receiver_type(expr),
+                _ => None,
+            },
 
-            Expr::Await(await_expr) => {
-                self.visit_expr(&await_expr.base);
+            // Element of an indexed collection
+            Expr::Index(index_expr) => {
+                index_element_type(&self.extract_receiver_type(&index_expr.expr)?)
             }
 
-            Expr::Try(try_expr) => {
-                self.visit_expr(&try_expr.expr);
+            _ => None,
+        }
+    }
+
+    // Helper method to lookup function return type for reference, preferring the unmodified
+    // return type which keeps the generic arguments needed for unwrapping. Generic parameters
+    // of the function are replaced by the types pinned by a turbofish at the call site.
+    fn lookup_function_return_type(&self, method_name: &str, turbofish: &[String]) -> Option<String> {
+        let function_info = find_function(method_name, &self.function_map)?;
+        if function_info.full_return_type.is_empty() {
+            return Some(function_info.return_type.clone());
+        }
+
+        let substitutions: HashMap<&str, &str> = function_info
+            .generic_params
+            .iter()
+            .map(String::as_str)
+            .zip(turbofish.iter().map(String::as_str))
+            .collect();
+        Some(
+            function_info
+                .full_return_type
+                .split(' ')
+                .map(|token| substitutions.get(token).copied().unwrap_or(token))
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    }
+
+    // Visit an expression and return its resolved type. Method chains and ?-unwrapped calls are
+    // typed while they are visited, so each part of the expression is only resolved once.
+    fn visit_typed_expr(&mut self, expr: &Expr) -> Option<String> {
+        match expr {
+            Expr::MethodCall(method_call) => self
+                .visit_method_chain(method_call)
+                .or_else(|| self.resolve_custom_type(expr)),
+
+            Expr::Try(try_expr) => self.visit_typed_expr(&try_expr.expr).map(|ty| unwrap_try(&ty)),
+
+            Expr::Paren(paren_expr) => self.visit_typed_expr(&paren_expr.expr),
+
+            _ => {
+                self.visit_expr(expr);
+                self.extract_receiver_type(expr)
             }
+        }
+    }
 
-            Expr::Closure(closure_expr) => {
-                self.visit_expr(&closure_expr.body);
+    // Visit a method call and return the resolved type of its result. The receiver of a
+    // chained call is typed by the recursive call itself, so each link of a long fluent
+    // chain is resolved once instead of once for every enclosing method call.
+    fn visit_method_chain(&mut self, node: &ExprMethodCall) -> Option<String> {
+        let receiver_type = self
+            .visit_typed_expr(&node.receiver)
+            .map(|ty| deref_smart_pointers(&self.expand_type_alias(ty)));
+
+        // Determine the fully qualified name
+        let method_name = node.method.to_string();
+        let line_number = self.call_line(node.method.span().start().line);
+        let qualified_name = self.prefer_trait_in_scope(qualify_method(receiver_type.clone(), &method_name));
+        self.call_arities
+            .insert((qualified_name.clone(), line_number), (node.args.len(), true));
+        self.written_names
+            .insert((qualified_name.clone(), line_number), method_name.clone());
+        self.record_call(qualified_name.clone(), line_number, node.span());
+
+        self.visit_call_args(&method_name, Some((qualified_name.clone(), line_number)), &node.args);
+
+        self.lookup_function_return_type(&qualified_name, &turbofish_types(node.turbofish.as_ref()))
+            .or_else(|| std_method_return_type(node, &method_name, receiver_type))
+    }
+
+    // Pick the implementation of a method from the trait in scope when the receiver type
+    // implements several traits with a method of the same name, e.g. `Raw::decode` from both
+    // Decode and Encode with only Decode imported. The name of the picked function is only
+    // distinct from the others when the function list was canonicalised.
+    fn prefer_trait_in_scope(&self, qualified_name: String) -> String {
+        let candidates = match self.trait_methods.get(&qualified_name) {
+            Some(candidates) if candidates.len() > 1 => candidates,
+            _ => return qualified_name,
+        };
+        let in_scope: Vec<&&FunctionInfo> = candidates
+            .iter()
+            .filter(|function| function.trait_name.as_ref().is_some_and(|name| self.traits_in_scope.contains(name)))
+            .collect();
+        match in_scope.as_slice() {
+            [function] => function.name.clone(),
+            _ => qualified_name,
+        }
+    }
+
+    // Replace a type alias declared in the harness by its target type
+    fn expand_type_alias(&self, ty: String) -> String {
+        match self.type_aliases.get(&type_name(&ty)) {
+            Some(target) => target.clone(),
+            None => ty,
+        }
+    }
+
+    // Visit the body of a generic harness function again for a call pinning its type
+    // parameters with a turbofish, e.g. `drive::<Parser>(d)`. The type parameters act as
+    // aliases of the pinned types, so `T::run(d)` is recorded as `Parser::run` at the line of
+    // the instantiating call. Only calls from a harness body or from another instantiation
+    // are followed, as elsewhere the turbofish may name unbound type parameters.
+    fn instantiate_generic_helper(&mut self, name: &str, turbofish: &[String], line: usize) {
+        if turbofish.is_empty()
+            || (self.harness_depth.is_none() && self.instantiating.is_empty())
+            || self.instantiating.iter().any(|helper| helper == name)
+        {
+            return;
+        }
+        let helper = match self.generic_helpers.get(name) {
+            Some(helper) => helper.clone(),
+            None => return,
+        };
+
+        let outer_aliases = self.type_aliases.clone();
+        for (param, ty) in helper.sig.generics.type_params().zip(turbofish) {
+            let ty = self.expand_type_alias(ty.clone());
+            self.type_aliases.insert(param.ident.to_string(), ty);
+        }
+        self.instantiating.push(name.to_string());
+        let previous_line = self.macro_line.replace(line);
+        self.visit_item_fn(&helper);
+        self.macro_line = previous_line;
+        self.instantiating.pop();
+        self.type_aliases = outer_aliases;
+    }
+
+    // Check whether a call path names a standard pass through wrapper, either qualified by its
+    // module like `std::hint::black_box` or bare like `black_box` when no project function of
+    // that name exists, e.g. a project defined `identity`
+    fn is_transparent_wrapper(&self, path: &SynPath) -> bool {
+        let segments: Vec<String> = path.segments.iter().map(|segment| segment.ident.to_string()).collect();
+        let Some((name, qualifier)) = segments.split_last() else {
+            return false;
+        };
+        let Some((module, _)) = TRANSPARENT_WRAPPERS.iter().find(|(_, wrapper)| wrapper == name) else {
+            return false;
+        };
+        match qualifier {
+            [] => find_function(name, &self.function_map).is_none(),
+            [prefix @ .., last] => {
+                last == module && (prefix.is_empty() || prefix == ["std"] || prefix == ["core"])
             }
+        }
+    }
 
-            Expr::Return(return_expr) => {
-                if let Some(inner_expr) = &return_expr.expr {
-                    self.visit_expr(inner_expr);
+    // Replace a leading type alias of a call path, e.g. `P::new` for `type P = Parser`
+    fn expand_path_alias(&self, name: String) -> String {
+        let (first, rest) = match name.split_once("::") {
+            Some(split) => split,
+            None => return name,
+        };
+        match self.type_aliases.get(first) {
+            Some(target) => format!("{}::{}", type_name(target), rest),
+            None => name,
+        }
+    }
+
+    // Visit the arguments of a call, which is the enclosing call of any calls made in them
+    // unless it is not recorded. Closures and async blocks passed to spawn functions are
+    // visited with the variable types of the spawning scope, which their bodies capture,
+    // without leaking their own variables back, or skipped if configured.
+    fn visit_call_args(
+        &mut self,
+        function_name: &str,
+        call: Option<(String, i32)>,
+        args: &Punctuated<Expr, Token![,]>,
+    ) {
+        let outer_call = match call {
+            Some(call) => self.enclosing_call.replace(call),
+            None => self.enclosing_call.clone(),
+        };
+        let is_spawn = SPAWN_FUNCTIONS.contains(&function_name);
+        for arg in args {
+            if is_spawn && matches!(arg, Expr::Closure(_) | Expr::Async(_)) {
+                if !self.config.skip_spawned_closures {
+                    let captured_types = self.variable_types.clone();
+                    self.visit_expr(arg);
+                    self.variable_types = captured_types;
                 }
+            } else {
+                self.visit_expr(arg);
             }
+        }
+        self.enclosing_call = outer_call;
+    }
 
-            Expr::Assign(assign_expr) => {
-                self.visit_expr(&assign_expr.left);
-                self.visit_expr(&assign_expr.right);
+    // Try extracting the local variable name creation
+    fn extract_variable_name(&self, pat: &syn::Pat) -> Option<String> {
+        if let syn::Pat::Ident(ident) = pat {
+            Some(ident.ident.to_string())
+        } else {
+            None
+        }
+    }
+
+    // Record the declared type of the variables bound by a pattern, matching tuple patterns
+    // against the element types of a tuple type and `&x` patterns against a reference type
+    fn bind_pattern_type(&mut self, pat: &syn::Pat, ty: &syn::Type) {
+        match (pat, ty) {
+            (syn::Pat::Ident(ident), _) => {
+                self.variable_types.insert(ident.ident.to_string(), type_to_string(ty));
             }
+            (syn::Pat::Reference(pat_reference), syn::Type::Reference(type_reference)) => {
+                self.bind_pattern_type(&pat_reference.pat, &type_reference.elem);
+            }
+            (syn::Pat::Tuple(pat_tuple), syn::Type::Tuple(type_tuple)) => {
+                for (elem_pat, elem_ty) in pat_tuple.elems.iter().zip(type_tuple.elems.iter()) {
+                    self.bind_pattern_type(elem_pat, elem_ty);
+                }
+            }
+            _ => {}
+        }
+    }
 
-            Expr::Unary(unary_expr) => {
-                self.visit_expr(&unary_expr.expr);
+    // Leave a scope binding the given names, which get back their type from before the scope.
+    // Every other variable keeps the type it was assigned in the scope.
+    fn end_scope(&mut self, outer_types: HashMap<String, String>, bindings: &HashSet<String>) {
+        for name in bindings {
+            match outer_types.get(name) {
+                Some(outer_type) => self.variable_types.insert(name.clone(), outer_type.clone()),
+                None => self.variable_types.remove(name),
+            };
+        }
+    }
+
+    // Visit the body of a closure, seeding the types of typed parameters such as `|op: Operation|`
+    fn visit_closure(&mut self, closure: &ExprClosure) {
+        for input in &closure.inputs {
+            if let syn::Pat::Type(pat_type) = input {
+                self.bind_pattern_type(&pat_type.pat, &pat_type.ty);
             }
+        }
+        self.visit_expr(&closure.body);
+    }
 
-            Expr::Binary(binary_expr) => {
-                self.visit_expr(&binary_expr.left);
-                self.visit_expr(&binary_expr.right);
+    // Visit the body of a macro, trying the shapes of harness macro bodies in turn: a closure
+    // with optional attributes, bare statements, a single expression, comma separated
+    // expressions and comma separated expressions labelled like `init: expr`
+    fn visit_macro_body(&mut self, mac: &Macro) {
+        if let Ok(closure) = mac.parse_body_with(parse_attributed_closure) {
+            self.visit_closure(&closure);
+        } else if let Ok(stmts) = mac.parse_body_with(Block::parse_within) {
+            for stmt in &stmts {
+                self.visit_stmt(stmt);
+            }
+        } else if let Ok(body) = mac.parse_body::<Expr>() {
+            self.visit_expr(&body);
+        } else if let Ok(args) = mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+            for arg in &args {
+                self.visit_expr(arg);
             }
+        } else if let Ok(args) = mac.parse_body_with(parse_labelled_args) {
+            for arg in &args {
+                self.visit_expr(arg);
+            }
+        } else if self.visit_nested_entry_macros(mac.tokens.clone()) {
+            // The body wraps a harness macro, whose calls are recorded by visiting it
+        } else if self.config.scan_macro_tokens {
+            self.scan_macro_tokens(mac.tokens.clone());
+        }
+    }
 
-            Expr::Field(field_expr) => {
-                self.visit_expr(&field_expr.base);
+    // Visit the body of a harness macro. A body that is a bare function path, as in
+    // `fuzz_target!(my_module::run)`, is the harness function itself. A function defined in the
+    // harness file is expanded in place, as its statements are the harness body. Any other path
+    // is recorded as a call passing the input, so it becomes the root of the call tree, unless
+    // it only resolves to a method of another type by its name, e.g. `Parser::run` for `run`.
+    fn visit_harness_macro_body(&mut self, mac: &Macro) {
+        let Ok(Expr::Path(path_expr)) = mac.parse_body::<Expr>() else {
+            self.visit_macro_body(mac);
+            return;
+        };
+        let local_function = path_expr
+            .path
+            .get_ident()
+            .and_then(|ident| self.local_functions.get(&ident.to_string()))
+            .cloned();
+        if let Some(local_function) = local_function {
+            self.visit_item_fn(&local_function);
+            return;
+        }
+
+        let name = self.expand_path_alias(path_to_string(path_expr.qself.as_ref(), &path_expr.path));
+        let line_number = self.call_line(path_expr.span().start().line);
+        if let Some(function) = find_function(&name, &self.function_map) {
+            let function_name = function.name.split('@').next().unwrap_or_default();
+            if function_name != name && !name.ends_with(&format!("::{}", function_name)) {
+                eprintln!(
+                    "Warning: harness function {} at line {} only matches the method {}",
+                    name, line_number, function_name
+                );
+                return;
             }
+        }
+        self.call_arities.insert((name.clone(), line_number), (1, false));
+        self.written_names
+            .insert((name.clone(), line_number), written_name(&path_expr));
+        self.record_call(name, line_number, path_expr.span());
+    }
 
-            Expr::Index(index_expr) => {
-                self.visit_expr(&index_expr.expr);
-                self.visit_expr(&index_expr.index);
+    // Search the tokens of a macro body that cannot be parsed for invocations of the harness
+    // entry macros, e.g. `fuzz_target!` wrapped in a setup macro with custom syntax, and visit
+    // each of them. Returns whether any entry macro was found.
+    fn visit_nested_entry_macros(&mut self, tokens: TokenStream) -> bool {
+        let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+        let mut found = false;
+        let mut index = 0;
+        while index < tokens.len() {
+            match (&tokens[index], tokens.get(index + 1), tokens.get(index + 2)) {
+                (TokenTree::Ident(name), Some(TokenTree::Punct(bang)), Some(TokenTree::Group(body)))
+                    if bang.as_char() == '!'
+                        && (name == "fuzz_target" || self.config.entry_macros.iter().any(|entry| name == entry)) =>
+                {
+                    let invocation: TokenStream = tokens[index..=index + 2].iter().cloned().collect();
+                    if let Ok(mac) = syn::parse2::<Macro>(invocation) {
+                        self.visit_macro(&mac);
+                        found = true;
+                    }
+                    index += 2;
+                }
+                (TokenTree::Group(group), _, _) => {
+                    found |= self.visit_nested_entry_macros(group.stream());
+                }
+                _ => {}
             }
+            index += 1;
+        }
+        found
+    }
 
-            Expr::Tuple(tuple_expr) => {
-                for elem in &tuple_expr.elems {
-                    self.visit_expr(elem);
+    // Last resort for macro bodies that are not rust expressions, recording every `path(`
+    // and `.method(` token sequence as a call. Method receivers are unknown, so only the
+    // method name is recorded.
+    fn scan_macro_tokens(&mut self, tokens: TokenStream) {
+        let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+        for (index, token) in tokens.iter().enumerate() {
+            match token {
+                TokenTree::Group(group) => {
+                    self.scan_macro_tokens(group.stream());
+                    continue;
                 }
+                TokenTree::Ident(ident) if !SCAN_KEYWORDS.contains(&ident.to_string().as_str()) => {}
+                _ => continue,
             }
 
-            Expr::Array(array_expr) => {
-                for elem in &array_expr.elems {
-                    self.visit_expr(elem);
+            let is_call = matches!(
+                tokens.get(index + 1),
+                Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis
+            );
+            if !is_call {
+                continue;
+            }
+
+            // Walk back over `::` separated path segments
+            let mut segments = vec![token.to_string()];
+            let mut start = index;
+            while start >= 3 {
+                match (&tokens[start - 3], &tokens[start - 2], &tokens[start - 1]) {
+                    (TokenTree::Ident(segment), TokenTree::Punct(first), TokenTree::Punct(second))
+                        if first.as_char() == ':' && second.as_char() == ':' =>
+                    {
+                        segments.insert(0, segment.to_string());
+                        start -= 3;
+                    }
+                    _ => break,
                 }
             }
 
-            Expr::Struct(struct_expr) => {
-                for field in &struct_expr.fields {
-                    self.visit_expr(&field.expr);
+            let name = segments.join("::");
+            let line_number = self.call_line(token.span().start().line);
+            self.heuristic_calls.push((name.clone(), line_number));
+            self.record_call(name, line_number, token.span());
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for FuzzTargetVisitor<'_> {
+    // visit implementation for the harness file, collecting the type aliases, structs, functions
+    // and macro_rules! macros declared at the top level first as they may be declared after the
+    // fuzz_target macro
+    fn visit_file(&mut self, file: &'ast syn::File) {
+        for item in &file.items {
+            match item {
+                syn::Item::Type(item_type) => self.visit_item_type(item_type),
+                syn::Item::Use(item_use) => self.visit_item_use(item_use),
+                syn::Item::Trait(item_trait) => {
+                    self.traits_in_scope.insert(item_trait.ident.to_string());
                 }
-                if let Some(rest) = &struct_expr.rest {
-                    self.visit_expr(rest);
+                syn::Item::Struct(item_struct) => self.visit_item_struct(item_struct),
+                // Statics and consts may be declared after the harness using them
+                syn::Item::Static(item_static) => {
+                    self.variable_types
+                        .insert(item_static.ident.to_string(), type_to_string(&item_static.ty));
                 }
+                syn::Item::Const(item_const) => {
+                    self.variable_types
+                        .insert(item_const.ident.to_string(), type_to_string(&item_const.ty));
+                }
+                syn::Item::Macro(item_macro) if item_macro.mac.path.is_ident("macro_rules") => {
+                    self.visit_item_macro(item_macro)
+                }
+                syn::Item::Fn(item_fn) if item_fn.sig.generics.type_params().next().is_some() => {
+                    self.generic_helpers
+                        .insert(item_fn.sig.ident.to_string(), item_fn.clone());
+                }
+                syn::Item::Fn(item_fn) => {
+                    self.local_functions
+                        .insert(item_fn.sig.ident.to_string(), item_fn.clone());
+                }
+                _ => {}
             }
+        }
+        syn::visit::visit_file(self, file);
+    }
 
-            Expr::Paren(paren_expr) => {
-                self.visit_expr(&paren_expr.expr);
+    // visit implementation for macro items. The single rule of a trivial macro_rules! macro
+    // is registered as a local macro pattern instead of visiting the definition, so calls are
+    // recorded where the macro is invoked.
+    fn visit_item_macro(&mut self, item_macro: &'ast syn::ItemMacro) {
+        match &item_macro.ident {
+            Some(ident) if item_macro.mac.path.is_ident("macro_rules") => {
+                if let Some(template) = macro_rules_template(&item_macro.mac) {
+                    self.local_macros.insert(ident.to_string(), template);
+                }
             }
+            _ => syn::visit::visit_item_macro(self, item_macro),
+        }
+    }
 
-            Expr::Macro(macro_expr) => {
-                if let Ok(parsed_body) = macro_expr.mac.parse_body::<Expr>() {
-                    self.visit_expr(&parsed_body);
+    // visit implementation for struct definitions, recording the declared field types which
+    // type field accesses on values of the struct
+    fn visit_item_struct(&mut self, item_struct: &'ast syn::ItemStruct) {
+        let fields = item_struct
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let name = match &field.ident {
+                    Some(ident) => ident.to_string(),
+                    None => index.to_string(),
+                };
+                (name, type_to_string(&field.ty))
+            })
+            .collect();
+        self.struct_fields.insert(item_struct.ident.to_string(), fields);
+    }
+
+    // visit implementation for type aliases
+    fn visit_item_type(&mut self, item_type: &'ast syn::ItemType) {
+        self.type_aliases
+            .insert(item_type.ident.to_string(), type_to_string(&item_type.ty));
+    }
+
+    // visit implementation for use declarations, recording every imported name as a trait
+    // possibly in scope. Names imported as `_` keep their own name.
+    fn visit_item_use(&mut self, item_use: &'ast syn::ItemUse) {
+        let mut pending = vec![&item_use.tree];
+        while let Some(tree) = pending.pop() {
+            match tree {
+                syn::UseTree::Path(use_path) => pending.push(&use_path.tree),
+                syn::UseTree::Name(use_name) => {
+                    self.traits_in_scope.insert(use_name.ident.to_string());
+                }
+                syn::UseTree::Rename(use_rename) => {
+                    self.traits_in_scope.insert(use_rename.ident.to_string());
                 }
+                syn::UseTree::Group(use_group) => pending.extend(use_group.items.iter()),
+                syn::UseTree::Glob(_) => {}
             }
+        }
+    }
 
-            Expr::Repeat(repeat_expr) => {
-                self.visit_expr(&repeat_expr.expr);
-            }
+    // visit implementation for static and const items, typing method calls on them by their
+    // declared type, e.g. `PARSER.run()` for `static PARSER: Parser`
+    fn visit_item_static(&mut self, item_static: &'ast syn::ItemStatic) {
+        self.variable_types
+            .insert(item_static.ident.to_string(), type_to_string(&item_static.ty));
+        syn::visit::visit_item_static(self, item_static);
+    }
 
-            Expr::Group(group_expr) => {
-                self.visit_expr(&group_expr.expr);
-            }
+    fn visit_item_const(&mut self, item_const: &'ast syn::ItemConst) {
+        self.variable_types
+            .insert(item_const.ident.to_string(), type_to_string(&item_const.ty));
+        syn::visit::visit_item_const(self, item_const);
+    }
 
-            _ => {
-                syn::visit::visit_expr(self, expr);
+    // visit implementation for harness local functions, seeding the types of the typed
+    // parameters for the body so that calls on a parameter like `p.run(d)` are resolved
+    fn visit_item_fn(&mut self, item_fn: &'ast syn::ItemFn) {
+        let outer_types = self.variable_types.clone();
+        for input in &item_fn.sig.inputs {
+            if let syn::FnArg::Typed(pat_type) = input {
+                self.bind_pattern_type(&pat_type.pat, &pat_type.ty);
             }
         }
+
+        // The type parameters are aliases of the pinned types while instantiating the function
+        let outer_params = self.unbound_type_params.len();
+        if self.instantiating.last() != Some(&item_fn.sig.ident.to_string()) {
+            self.unbound_type_params
+                .extend(item_fn.sig.generics.type_params().map(|param| param.ident.to_string()));
+        }
+        syn::visit::visit_item_fn(self, item_fn);
+        self.unbound_type_params.truncate(outer_params);
+        self.variable_types = outer_types;
     }
-}
 
-// Process the correct full qualified name for rust functions/methods
-fn path_to_string(path: &SynPath) -> String {
-    path.segments
-        .iter()
-        .map(|s| s.ident.to_string())
-        .collect::<Vec<_>>()
-        .join("::")
-}
+    // `let` bindings made inside a block, including those shadowing an outer variable, go out
+    // of scope at its end, so calls after the block see the outer types again. Assignments to
+    // outer variables inside the block are kept.
+    fn visit_block(&mut self, block: &'ast syn::Block) {
+        let outer_types = self.variable_types.clone();
+        let mut bindings = HashSet::new();
+        for stmt in &block.stmts {
+            if let Stmt::Local(local) = stmt {
+                pattern_bindings(&local.pat, &mut bindings);
+            }
+        }
+        syn::visit::visit_block(self, block);
+        self.end_scope(outer_types, &bindings);
+    }
 
-// Build and output the call tree in .data format following LLVM approach
-fn build_call_tree(
-    function_name: &str,
-    function_map: &HashMap<String, &FunctionInfo>,
-    call_path: &str,
-    mut line_number: i32,
-    visited: &mut HashSet<String>,
-    depth: usize,
-) -> Option<String> {
-    let mut result = String::new();
-    let indent = "  ".repeat(depth + 1);
+    // visit implementation method for locating the statement in the fuzz_target macro and
+    // visiting the calls inside any other macro invocation
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        if self.visit_include_macro(mac) {
+            return;
+        }
 
-    if line_number == 0 {
-        line_number = -1;
+        // Only the entry macros mark the harness entry. Other registered and harness local
+        // macros expand in place, in the statement of their caller.
+        let macro_name = mac.path.segments.last().unwrap().ident.to_string();
+        if macro_name == "fuzz_target" || self.config.entry_macros.contains(&macro_name) {
+            self.entry_line
+                .get_or_insert(normalise_line(mac.path.span().start().line));
+            if self.entry_signature.is_none() {
+                self.entry_signature = harness_closure(mac).map(|closure| EntrySignature::from_closure(&closure));
+            }
+            self.visit_harness_body(|visitor| {
+                if !visitor.visit_registered_macro(mac) {
+                    visitor.visit_harness_macro_body(mac);
+                }
+            });
+        } else if !self.visit_registered_macro(mac) {
+            self.visit_macro_body(mac);
+        }
     }
 
-    if let Some(function_info) = find_function(function_name, function_map) {
-        if visited.contains(&function_info.name) {
-            return None;
+    // visit implementation for statements, shared by all blocks including the closure body
+    // of the fuzz_target macro, so every statement goes through the type-aware logic
+    fn visit_stmt(&mut self, stmt: &'ast Stmt) {
+        let top_level = self.harness_depth == Some(0);
+        if let Some(depth) = self.harness_depth.as_mut() {
+            *depth += 1;
         }
 
-        visited.insert(function_info.name.clone());
+        match stmt {
+            Stmt::Local(local_stmt) => {
+                self.visit_local(local_stmt);
+            }
 
-        // Insert the call tree line
-        result.push_str(&format!(
-            "{}{} {} linenumber={}\n",
-            indent, function_info.name.replace(" ", ""), call_path, line_number
-        ));
+            Stmt::Expr(inner_expr, _) => {
+                self.visit_expr(inner_expr);
+            }
 
-        // Recursively process all function call trees
-        for callsite in &function_info.callsites {
-            let call_location: Vec<&str> = callsite.src.split(',').collect();
-            if call_location.len() >= 2 {
-                let callsite_path = call_location[0];
-                let callsite_line = call_location[1].parse::<i32>().unwrap_or(-1);
+            Stmt::Item(item) => {
+                syn::visit::visit_item(self, item);
+            }
 
-                if let Some(call_tree) = build_call_tree(
-                    &callsite.dst,
-                    function_map,
-                    callsite_path,
-                    callsite_line,
-                    visited,
-                    depth + 1,
-                ) {
-                    result.push_str(&call_tree);
-                }
+            Stmt::Macro(macro_stmt) => {
+                self.visit_macro(&macro_stmt.mac);
             }
         }
-    } else {
-        result.push_str(&format!(
-            "{}{} {} linenumber={}\n",
-            indent, function_name.replace(" ", ""), call_path, line_number
-        ));
-    }
-    if result.is_empty() {
-        None
-    } else {
-        Some(result)
-    }
-}
 
-// Search for the functions in the analysis result and exclude functions/methods not from the project
-fn find_function<'a>(
-    function_name: &str,
-    function_map: &'a HashMap<String, &'a FunctionInfo>,
-) -> Option<&'a FunctionInfo> {
-    // Exact match
-    if let Some(func) = function_map.get(function_name) {
-        return Some(func);
+        if let Some(depth) = self.harness_depth.as_mut() {
+            *depth -= 1;
+        }
+        if top_level {
+            self.flush_statement_roots();
+        }
     }
 
-    // Match any key that ends with function_name
-    if let Some((_, func)) = function_map.iter().find(|(key, _)| key.ends_with(function_name)) {
-        return Some(func);
+    // visit implementation method for processing each function expression
+    fn visit_expr_call(&mut self, node: &'ast ExprCall) {
+        let mut call = None;
+        if let Expr::Path(ExprPath { qself, path, .. }) = &*node.func {
+            if qself.is_some() || !self.is_transparent_wrapper(path) {
+                let qualified_name = self.expand_path_alias(path_to_string(qself.as_ref(), path));
+                let line_number = self.call_line(node.func.span().start().line);
+                self.call_arities
+                    .insert((qualified_name.clone(), line_number), (node.args.len(), false));
+                self.written_names
+                    .insert((qualified_name.clone(), line_number), written_name(&node.func));
+                self.record_call(qualified_name.clone(), line_number, node.span());
+                call = Some((qualified_name, line_number));
+            }
+            if let Some(segment) = path.segments.last() {
+                if let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments {
+                    self.instantiate_generic_helper(
+                        &segment.ident.to_string(),
+                        &turbofish_types(Some(arguments)),
+                        node.func.span().start().line,
+                    );
+                }
+            }
+        } else {
+            // Calls made while computing the callee itself, e.g. inside an immediately invoked
+            // closure `(|| -> Result<_, _> { a()?.b() })()` emulating a try block
+            self.visit_expr(&node.func);
+        }
+
+        let function_name = match &*node.func {
+            Expr::Path(ExprPath { path, .. }) => path.segments.last().map(|segment| segment.ident.to_string()),
+            _ => None,
+        };
+        self.visit_call_args(function_name.as_deref().unwrap_or_default(), call, &node.args);
     }
 
-    // Split and check segments from the right side
-    let segments: Vec<&str> = function_name.split("::").collect();
-    for i in 0..segments.len() {
-        let partial_name = segments[i..].join("::");
-        if let Some(func) = function_map.get(&partial_name) {
-            return Some(func);
-        }
+    // visit implementation method for handling echo method experssion
+    fn visit_expr_method_call(&mut self, node: &'ast ExprMethodCall) {
+        self.visit_method_chain(node);
     }
 
-    // No match found
-    None
+    // visit implementation for local variables
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        if let Some(init_expr) = &local.init {
+            let init_type = self.visit_typed_expr(&init_expr.expr);
+            if let Some((_, diverge_expr)) = &init_expr.diverge {
+                self.visit_expr(diverge_expr);
+            }
+
+            // A shadowing binding of unknown type must not keep the type of the previous one
+            if let Some(var_name) = self.extract_variable_name(&local.pat) {
+                match init_type {
+                    Some(var_type) => self.variable_types.insert(var_name, var_type),
+                    None => self.variable_types.remove(&var_name),
+                };
+            }
+        }
+
+        // A type annotation takes precedence over the type of the initialiser
+        if let syn::Pat::Type(pat_type) = &local.pat {
+            self.bind_pattern_type(&pat_type.pat, &pat_type.ty);
+        }
+    }
+
+    // General method ensure visiting all kinds of Expr that could call functions/methods
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        match expr {
+            Expr::Call(call_expr) => {
+                self.visit_expr_call(call_expr);
+            }
+
+            Expr::MethodCall(method_call_expr) => {
+                self.visit_expr_method_call(method_call_expr);
+            }
+
+            Expr::Block(block_expr) => {
+                self.visit_block(&block_expr.block);
+            }
+
+            // Nightly `try { }` blocks, whose statements are scoped like any other block
+            Expr::TryBlock(try_block_expr) => {
+                self.visit_block(&try_block_expr.block);
+            }
+
+            Expr::If(if_expr) => {
+                // The condition may be a let-chain joined by `&&`, which is covered by the
+                // Binary and Let arms, and an `else if` branch is itself an Expr::If
+                let outer_types = self.variable_types.clone();
+                let mut bindings = HashSet::new();
+                condition_bindings(&if_expr.cond, &mut bindings);
+                self.visit_expr(&if_expr.cond);
+                self.visit_block(&if_expr.then_branch);
+                self.end_scope(outer_types, &bindings);
+                if let Some((_, else_branch)) = &if_expr.else_branch {
+                    self.visit_expr(else_branch);
+                }
+            }
+
+            Expr::Let(let_expr) => {
+                // Bind `Some(x)` and `Ok(x)` patterns to the unwrapped type of the scrutinee
+                let scrutinee_type = self.visit_typed_expr(&let_expr.expr);
+                if let (syn::Pat::TupleStruct(pat_tuple), Some(ty)) = (&*let_expr.pat, scrutinee_type) {
+                    let is_try_variant = pat_tuple
+                        .path
+                        .segments
+                        .last()
+                        .is_some_and(|segment| segment.ident == "Some" || segment.ident == "Ok");
+                    if is_try_variant && pat_tuple.elems.len() == 1 {
+                        if let Some(var_name) = self.extract_variable_name(&pat_tuple.elems[0]) {
+                            self.variable_types.insert(var_name, unwrap_try(&ty));
+                        }
+                    }
+                }
+            }
+
+            Expr::Match(match_expr) => {
+                self.visit_expr(&match_expr.expr);
+                for arm in &match_expr.arms {
+                    if let Some((_, guard)) = &arm.guard {
+                        self.visit_expr(guard);
+                    }
+                    self.visit_expr(&arm.body);
+                }
+            }
+
+            Expr::While(while_expr) => {
+                // A `while let` binds its pattern for the body only
+                let outer_types = self.variable_types.clone();
+                let mut bindings = HashSet::new();
+                condition_bindings(&while_expr.cond, &mut bindings);
+                self.visit_expr(&while_expr.cond);
+                self.visit_block(&while_expr.body);
+                self.end_scope(outer_types, &bindings);
+            }
+
+            Expr::ForLoop(for_loop_expr) => {
+                // Bind the loop pattern to the item type of the iterated expression for the body,
+                // keeping the borrow of a collection looped over by reference like `&items`
+                let mut iterated_type = self.visit_typed_expr(&for_loop_expr.expr);
+                if let Expr::Reference(reference) = &*for_loop_expr.expr {
+                    let borrow = if reference.mutability.is_some() { "& mut" } else { "&" };
+                    iterated_type = iterated_type.map(|ty| format!("{} {}", borrow, ty));
+                }
+                let outer_types = self.variable_types.clone();
+                let mut bindings = HashSet::new();
+                pattern_bindings(&for_loop_expr.pat, &mut bindings);
+                let item_type = iterated_type.as_deref().and_then(iterator_item_type);
+                if let Some(item_type) = item_type.and_then(|ty| parse_type(&ty)) {
+                    self.bind_pattern_type(&for_loop_expr.pat, &item_type);
+                }
+                self.visit_block(&for_loop_expr.body);
+                self.end_scope(outer_types, &bindings);
+            }
+
+            Expr::Await(await_expr) => {
+                self.visit_expr(&await_expr.base);
+            }
+
+            Expr::Try(try_expr) => {
+                self.visit_expr(&try_expr.expr);
+            }
+
+            Expr::Closure(closure_expr) => {
+                self.visit_closure(closure_expr);
+            }
+
+            Expr::Return(return_expr) => {
+                if let Some(inner_expr) = &return_expr.expr {
+                    self.visit_expr(inner_expr);
+                }
+            }
+
+            Expr::Assign(assign_expr) => {
+                self.visit_expr(&assign_expr.left);
+                let assigned_type = self.visit_typed_expr(&assign_expr.right);
+
+                // Assigning to a variable keeps its type, but it may only be known from here on
+                if let (Expr::Path(ExprPath { qself: None, path, .. }), Some(var_type)) =
+                    (&*assign_expr.left, assigned_type)
+                {
+                    if let Some(var_name) = path.get_ident() {
+                        self.variable_types.insert(var_name.to_string(), var_type);
+                    }
+                }
+            }
+
+            Expr::Unary(unary_expr) => {
+                self.visit_expr(&unary_expr.expr);
+            }
+
+            Expr::Binary(binary_expr) => {
+                self.visit_expr(&binary_expr.left);
+                self.visit_expr(&binary_expr.right);
+            }
+
+            Expr::Field(field_expr) => {
+                self.visit_expr(&field_expr.base);
+            }
+
+            Expr::Index(index_expr) => {
+                self.visit_expr(&index_expr.expr);
+                self.visit_expr(&index_expr.index);
+            }
+
+            Expr::Range(range_expr) => {
+                if let Some(start) = &range_expr.start {
+                    self.visit_expr(start);
+                }
+                if let Some(end) = &range_expr.end {
+                    self.visit_expr(end);
+                }
+            }
+
+            Expr::Tuple(tuple_expr) => {
+                for elem in &tuple_expr.elems {
+                    self.visit_expr(elem);
+                }
+            }
+
+            Expr::Array(array_expr) => {
+                for elem in &array_expr.elems {
+                    self.visit_expr(elem);
+                }
+            }
+
+            Expr::Struct(struct_expr) => {
+                let struct_name = type_name(&self.expand_path_alias(path_to_string(
+                    struct_expr.qself.as_ref(),
+                    &struct_expr.path,
+                )));
+                for field in &struct_expr.fields {
+                    self.visit_expr(&field.expr);
+
+                    // An untyped local moved into a field takes the declared field type
+                    let field_type = self
+                        .struct_fields
+                        .get(&struct_name)
+                        .and_then(|fields| fields.get(&member_name(&field.member)))
+                        .cloned();
+                    if let (Some(field_type), Expr::Path(expr_path)) = (field_type, &field.expr) {
+                        if let Some(var_name) = expr_path.path.get_ident() {
+                            self.variable_types
+                                .entry(var_name.to_string())
+                                .or_insert(field_type);
+                        }
+                    }
+                }
+                if let Some(rest) = &struct_expr.rest {
+                    self.visit_expr(rest);
+                }
+            }
+
+            Expr::Paren(paren_expr) => {
+                self.visit_expr(&paren_expr.expr);
+            }
+
+            Expr::Macro(macro_expr) => {
+                self.visit_macro(&macro_expr.mac);
+            }
+
+            Expr::Repeat(repeat_expr) => {
+                self.visit_expr(&repeat_expr.expr);
+            }
+
+            Expr::Group(group_expr) => {
+                self.visit_expr(&group_expr.expr);
+            }
+
+            _ => {
+                syn::visit::visit_expr(self, expr);
+            }
+        }
+    }
+}
+
+// Collect the names of the variables bound by a pattern
+fn pattern_bindings(pat: &syn::Pat, bindings: &mut HashSet<String>) {
+    match pat {
+        syn::Pat::Ident(pat_ident) => {
+            bindings.insert(pat_ident.ident.to_string());
+            if let Some((_, subpat)) = &pat_ident.subpat {
+                pattern_bindings(subpat, bindings);
+            }
+        }
+        syn::Pat::Type(pat_type) => pattern_bindings(&pat_type.pat, bindings),
+        syn::Pat::Reference(pat_reference) => pattern_bindings(&pat_reference.pat, bindings),
+        syn::Pat::Paren(pat_paren) => pattern_bindings(&pat_paren.pat, bindings),
+        syn::Pat::Tuple(pat_tuple) => pat_tuple.elems.iter().for_each(|elem| pattern_bindings(elem, bindings)),
+        syn::Pat::TupleStruct(pat_tuple) => pat_tuple.elems.iter().for_each(|elem| pattern_bindings(elem, bindings)),
+        syn::Pat::Slice(pat_slice) => pat_slice.elems.iter().for_each(|elem| pattern_bindings(elem, bindings)),
+        syn::Pat::Or(pat_or) => pat_or.cases.iter().for_each(|case| pattern_bindings(case, bindings)),
+        syn::Pat::Struct(pat_struct) => {
+            for field in &pat_struct.fields {
+                pattern_bindings(&field.pat, bindings);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Collect the names bound by the `let` patterns of an if or while condition, which may be a
+// let-chain joined by `&&`
+fn condition_bindings(cond: &Expr, bindings: &mut HashSet<String>) {
+    match cond {
+        Expr::Let(let_expr) => pattern_bindings(&let_expr.pat, bindings),
+        Expr::Binary(binary_expr) => {
+            condition_bindings(&binary_expr.left, bindings);
+            condition_bindings(&binary_expr.right, bindings);
+        }
+        Expr::Paren(paren_expr) => condition_bindings(&paren_expr.expr, bindings),
+        _ => {}
+    }
+}
+
+// Process the correct full qualified name for rust functions/methods. For fully qualified
+// syntax like `<Parser as Decode>::decode` the concrete self type replaces the trait segments.
+fn path_to_string(qself: Option<&QSelf>, path: &SynPath) -> String {
+    let mut segments = Vec::new();
+    let mut position = 0;
+    if let Some(qself) = qself {
+        let self_type = match &*qself.ty {
+            syn::Type::Path(type_path) => type_path
+                .path
+                .segments
+                .last()
+                .map(|seg| seg.ident.to_string())
+                .unwrap_or_default(),
+            ty => quote::ToTokens::to_token_stream(ty).to_string().replace(' ', ""),
+        };
+        segments.push(self_type);
+        position = qself.position;
+    }
+
+    segments.extend(path.segments.iter().skip(position).map(|s| s.ident.to_string()));
+    segments.join("::")
+}
+
+// Name a struct field as recorded for harness structs, using the index for tuple struct fields
+fn member_name(member: &syn::Member) -> String {
+    match member {
+        syn::Member::Named(ident) => ident.to_string(),
+        syn::Member::Unnamed(index) => index.index.to_string(),
+    }
+}
+
+// Retrieve the types given in a turbofish, e.g. `Parser` for `decode::<Parser>`
+fn turbofish_types(arguments: Option<&syn::AngleBracketedGenericArguments>) -> Vec<String> {
+    arguments
+        .into_iter()
+        .flat_map(|arguments| arguments.args.iter())
+        .filter_map(|arg| match arg {
+            syn::GenericArgument::Type(ty) => Some(type_to_string(ty)),
+            _ => None,
+        })
+        .collect()
+}
+
+// Qualify a method name with the name of its receiver type when the type is known
+fn qualify_method(receiver_type: Option<String>, method_name: &str) -> String {
+    match receiver_type {
+        Some(receiver) => format!("{}::{}", type_name(&receiver), method_name),
+        None => method_name.to_string(),
+    }
+}
+
+// Parse a closure preceded by optional outer attributes, e.g. `#[cfg(fuzzing)] |data| {}`
+fn parse_attributed_closure(input: ParseStream) -> syn::Result<ExprClosure> {
+    input.call(Attribute::parse_outer)?;
+    input.parse()
+}
+
+// Retrieve the closure of a harness macro body, which may follow labelled arguments as in
+// `fuzz_target!(init: setup(), |data: &[u8]| {})`
+fn harness_closure(mac: &Macro) -> Option<ExprClosure> {
+    if let Ok(closure) = mac.parse_body_with(parse_attributed_closure) {
+        return Some(closure);
+    }
+    mac.parse_body_with(parse_labelled_args).ok()?.into_iter().find_map(|arg| match arg {
+        Expr::Closure(closure) => Some(closure),
+        _ => None,
+    })
+}
+
+// Parse comma separated expressions which may be labelled, as in
+// `fuzz_target!(init: setup(), |data| {})`
+fn parse_labelled_args(input: ParseStream) -> syn::Result<Vec<Expr>> {
+    let mut args = Vec::new();
+    while !input.is_empty() {
+        if input.peek(syn::Ident) && input.peek2(Token![:]) && !input.peek2(Token![::]) {
+            input.parse::<syn::Ident>()?;
+            input.parse::<Token![:]>()?;
+        }
+        args.push(input.parse()?);
+        if input.is_empty() {
+            break;
+        }
+        input.parse::<Token![,]>()?;
+    }
+    Ok(args)
+}
+
+// Convert a macro_rules! definition with a single rule matching comma separated fragments,
+// e.g. `($d:expr) => { real_fn($d) }`, into a macro pattern template like `real_fn($1)`.
+// Definitions with several rules, repetitions or literal tokens in the matcher are skipped.
+fn macro_rules_template(mac: &Macro) -> Option<String> {
+    let tokens: Vec<TokenTree> = mac.tokens.clone().into_iter().collect();
+    let (matcher, body) = match tokens.as_slice() {
+        [TokenTree::Group(matcher), TokenTree::Punct(eq), TokenTree::Punct(gt), TokenTree::Group(body)]
+        | [TokenTree::Group(matcher), TokenTree::Punct(eq), TokenTree::Punct(gt), TokenTree::Group(body), TokenTree::Punct(_)]
+            if eq.as_char() == '=' && gt.as_char() == '>' =>
+        {
+            (matcher, body)
+        }
+        _ => return None,
+    };
+
+    // Collect the fragment names of the matcher in order, e.g. `$d:expr, $n:ident`
+    let mut fragments = Vec::new();
+    let matcher_tokens: Vec<TokenTree> = matcher.stream().into_iter().collect();
+    for fragment in matcher_tokens.split(|token| matches!(token, TokenTree::Punct(p) if p.as_char() == ',')) {
+        match fragment {
+            [TokenTree::Punct(dollar), TokenTree::Ident(name), TokenTree::Punct(colon), TokenTree::Ident(_)]
+                if dollar.as_char() == '$' && colon.as_char() == ':' =>
+            {
+                fragments.push(name.to_string());
+            }
+            [] => {}
+            _ => return None,
+        }
+    }
+
+    let mut template = String::new();
+    write_template_tokens(body.stream(), &fragments, &mut template);
+    Some(template)
+}
+
+// Write the tokens of a macro_rules! body as template source, replacing each `$name`
+// fragment by its position in the matcher, e.g. `$1`
+fn write_template_tokens(tokens: TokenStream, fragments: &[String], result: &mut String) {
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match &token {
+            TokenTree::Punct(punct) if punct.as_char() == '$' => {
+                let position = match tokens.peek() {
+                    Some(TokenTree::Ident(name)) => fragments.iter().position(|fragment| name == fragment),
+                    _ => None,
+                };
+                match position {
+                    Some(position) => {
+                        tokens.next();
+                        result.push_str(&format!("${} ", position + 1));
+                    }
+                    None => result.push_str("$ "),
+                }
+            }
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::None => ("", ""),
+                };
+                result.push_str(open);
+                write_template_tokens(group.stream(), fragments, result);
+                result.push_str(close);
+                result.push(' ');
+            }
+            TokenTree::Punct(punct) if punct.spacing() == proc_macro2::Spacing::Joint => {
+                result.push(punct.as_char());
+            }
+            _ => {
+                result.push_str(&token.to_string());
+                result.push(' ');
+            }
+        }
+    }
+}
+
+// Determine the result type of a standard method call that is not defined by the project
+fn std_method_return_type(
+    node: &ExprMethodCall,
+    method_name: &str,
+    receiver_type: Option<String>,
+) -> Option<String> {
+    if IDENTITY_METHODS.contains(&method_name) {
+        return receiver_type;
+    }
+    if UNWRAP_METHODS.contains(&method_name) {
+        return receiver_type.map(|ty| unwrap_try(&ty));
+    }
+    if TRY_CONVERSION_METHODS.contains(&method_name) {
+        return receiver_type.map(|ty| format!("Result < {} >", unwrap_try(&ty)));
+    }
+    if method_name == "ok" {
+        return receiver_type.map(|ty| format!("Option < {} >", unwrap_try(&ty)));
+    }
+    if TRY_PRESERVING_METHODS.contains(&method_name)
+        && receiver_type.as_deref().is_some_and(|ty| matches!(type_name(ty).as_str(), "Option" | "Result"))
+    {
+        return receiver_type;
+    }
+    if let Some(guard) = INTERIOR_ACCESS_METHODS
+        .iter()
+        .find(|(cell, method, _)| *method == method_name && receiver_type.as_deref().is_some_and(|ty| type_name(ty) == *cell))
+        .map(|(_, _, guard)| guard)
+    {
+        let inner = generic_arguments(&receiver_type?).into_iter().next()?;
+        return Some(guard.replace("$T", &inner));
+    }
+    if REFERENCE_METHODS.contains(&method_name) {
+        let ty = receiver_type?;
+        let wrapper = type_name(&ty);
+        return match wrapper.as_str() {
+            "Option" | "Result" => Some(format!("{} < & {} >", wrapper, unwrap_try(&ty))),
+            _ => Some(format!("& {}", ty)),
+        };
+    }
+    if ITERATOR_METHODS.contains(&method_name) {
+        let ty = receiver_type?;
+        let item_type = match method_name {
+            "iter" => iterator_item_type(&format!("& {}", ty))?,
+            "iter_mut" => iterator_item_type(&format!("& mut {}", ty))?,
+            "values" => format!("& {}", index_element_type(&ty)?),
+            "values_mut" => format!("& mut {}", index_element_type(&ty)?),
+            _ => iterator_item_type(&ty)?,
+        };
+        return Some(format!("impl Iterator < Item = {} >", item_type));
+    }
+    if ITERATOR_ADAPTER_METHODS.contains(&method_name) {
+        let ty = receiver_type?;
+        return iterator_item_type(&ty).is_some().then_some(ty);
+    }
+    if method_name == "enumerate" {
+        let item_type = iterator_item_type(&receiver_type?)?;
+        return Some(format!("impl Iterator < Item = ( usize , {} ) >", item_type));
+    }
+    if ITERATOR_CONSUMER_METHODS.contains(&method_name) && receiver_type.as_deref().is_some_and(is_iterator_type) {
+        let item_type = iterator_item_type(&receiver_type?)?;
+        return Some(format!("Option < {} >", item_type));
+    }
+    if ELEMENT_ACCESS_METHODS.contains(&method_name) {
+        let element_type = index_element_type(&receiver_type?)?;
+        return Some(format!("Option < & {} >", element_type));
+    }
+
+    // The turbofish names the produced type, e.g. collect::<Vec<Item>>() or into::<Parser>()
+    let target = turbofish_types(node.turbofish.as_ref()).into_iter().next()?;
+    match method_name {
+        "collect" | "into" => Some(target),
+        "try_into" | "parse" | "arbitrary" => Some(format!("Result < {} >", target)),
+        _ => None,
+    }
+}
+
+// Unknown span locations are reported as line 0, which is always recorded as -1 instead
+fn normalise_line(line: usize) -> i32 {
+    if line == 0 {
+        -1
+    } else {
+        line as i32
+    }
+}
+
+// Base struct for a node of the call tree of a fuzzing harness. A node revisiting a
+// function that is already expanded elsewhere in the tree is kept without children, so
+// recursion can be shown as a back edge instead of being expanded infinitely.
+pub struct CallTreeNode<'a> {
+    pub name: String,
+    pub call_path: String,
+    pub line_number: i32,
+    pub function: Option<&'a FunctionInfo>,
+    pub revisit: bool,
+    // Number of arguments passed at the call, if known
+    pub arg_count: Option<usize>,
+    // Callee as written at a harness call, e.g. `run` for the node `Parser::run`
+    pub written_name: Option<String>,
+    pub children: Vec<CallTreeNode<'a>>,
+}
+
+// Base struct for the set of functions already expanded in the call trees of a harness. With
+// a capacity, the names move into a bloom filter of one 64 bit word per capacity entry once
+// the capacity is exceeded, so a false positive only marks a function as a revisit.
+struct VisitedSet {
+    names: HashSet<String>,
+    capacity: Option<usize>,
+    bloom: Vec<u64>,
+}
+
+impl VisitedSet {
+    fn new(capacity: Option<usize>) -> Self {
+        VisitedSet {
+            names: HashSet::new(),
+            capacity,
+            bloom: Vec::new(),
+        }
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        if self.bloom.is_empty() {
+            return self.names.contains(name);
+        }
+        self.bloom_bits(name)
+            .iter()
+            .all(|&bit| self.bloom[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    fn insert(&mut self, name: &str) {
+        if self.bloom.is_empty() {
+            match self.capacity {
+                Some(capacity) if self.names.len() >= capacity => {
+                    // Spill the exact names into the bloom filter and release their memory
+                    self.bloom = vec![0; capacity.max(1)];
+                    for spilled in std::mem::take(&mut self.names) {
+                        self.insert_bloom(&spilled);
+                    }
+                }
+                _ => {
+                    self.names.insert(name.to_string());
+                    return;
+                }
+            }
+        }
+        self.insert_bloom(name);
+    }
+
+    fn insert_bloom(&mut self, name: &str) {
+        for bit in self.bloom_bits(name) {
+            self.bloom[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    // Approximate number of bytes held by the visited names or by the bloom filter
+    fn memory_size(&self) -> usize {
+        let names: usize = self.names.iter().map(|name| name.capacity()).sum();
+        names + self.names.capacity() * std::mem::size_of::<String>() + self.bloom.len() * 8
+    }
+
+    // Positions of the bloom filter bits of a name, derived from differently seeded hashes
+    fn bloom_bits(&self, name: &str) -> [usize; BLOOM_HASHES] {
+        let bit_count = self.bloom.len() * 64;
+        std::array::from_fn(|seed| {
+            let mut hasher = DefaultHasher::new();
+            seed.hash(&mut hasher);
+            name.hash(&mut hasher);
+            (hasher.finish() % bit_count as u64) as usize
+        })
+    }
+}
+
+// Build the call tree of a function following the LLVM approach. The external level counts
+// the consecutive calls into dependency crates leading to this call, which are cut once they
+// go deeper than the configured external depth.
+fn build_call_tree<'a>(
+    function_name: &str,
+    function_map: &HashMap<String, &'a FunctionInfo>,
+    call_path: &str,
+    line_number: i32,
+    visited: &mut VisitedSet,
+    external_level: usize,
+    config: &CallTreeConfig,
+) -> Option<CallTreeNode<'a>> {
+    if let Some(function_info) = find_function(function_name, function_map) {
+        let external_level = if is_external_function(function_info, config) {
+            external_level + 1
+        } else {
+            0
+        };
+        if config.external_depth.is_some_and(|depth| external_level > depth) {
+            return None;
+        }
+
+        let mut node = CallTreeNode {
+            name: function_info.name.clone(),
+            call_path: call_path.to_string(),
+            line_number,
+            function: Some(function_info),
+            revisit: visited.contains(&function_info.name),
+            arg_count: None,
+            written_name: None,
+            children: Vec::new(),
+        };
+        if node.revisit {
+            return Some(node);
+        }
+
+        visited.insert(&function_info.name);
+
+        // Recursively process all function call trees
+        for callsite in &function_info.callsites {
+            let call_location: Vec<&str> = callsite.src.split(',').collect();
+            if call_location.len() >= 2 {
+                let callsite_path = call_location[0];
+                let callsite_line = call_location[1].parse::<usize>().map_or(-1, normalise_line);
+
+                if let Some(mut call_tree) = build_call_tree(
+                    &callsite.dst,
+                    function_map,
+                    callsite_path,
+                    callsite_line,
+                    visited,
+                    external_level,
+                    config,
+                ) {
+                    call_tree.arg_count = Some(callsite.arg_count);
+                    node.children.push(call_tree);
+                }
+            }
+        }
+        Some(node)
+    } else {
+        build_external_node(function_name, call_path, line_number, config)
+    }
+}
+
+// Build the leaf node of a blocklisted root call, which is recorded without expanding the
+// calls of the function
+fn build_blocked_root_node<'a>(
+    function_name: &str,
+    function_map: &HashMap<String, &'a FunctionInfo>,
+    call_path: &str,
+    line_number: i32,
+) -> CallTreeNode<'a> {
+    let function = find_function(function_name, function_map);
+    CallTreeNode {
+        name: function.map_or_else(|| function_name.to_string(), |function| function.name.clone()),
+        call_path: call_path.to_string(),
+        line_number,
+        function,
+        revisit: false,
+        arg_count: None,
+        written_name: None,
+        children: Vec::new(),
+    }
+}
+
+// Build the leaf node of a call to a function outside of the project, if it is allowed
+fn build_external_node<'a>(
+    function_name: &str,
+    call_path: &str,
+    line_number: i32,
+    config: &CallTreeConfig,
+) -> Option<CallTreeNode<'a>> {
+    if !is_allowed_external(function_name, config) {
+        return None;
+    }
+
+    Some(CallTreeNode {
+        name: function_name.to_string(),
+        call_path: call_path.to_string(),
+        line_number,
+        function: None,
+        revisit: false,
+        arg_count: None,
+        written_name: None,
+        children: Vec::new(),
+    })
+}
+
+// Count the lines of a call tree in the .data output, which leaves out revisited functions
+fn count_call_tree_lines(node: &CallTreeNode) -> usize {
+    if node.revisit {
+        return 0;
+    }
+    1 + node.children.iter().map(count_call_tree_lines).sum::<usize>()
+}
+
+// Calculate the number of call levels below and including the given node as listed in the
+// .data output, which leaves out revisited functions
+fn call_tree_depth(node: &CallTreeNode) -> usize {
+    if node.revisit {
+        return 0;
+    }
+    1 + node.children.iter().map(call_tree_depth).max().unwrap_or(0)
+}
+
+// Sum the complexity of the resolved functions of a call tree. Every function is expanded
+// only once across the call trees of a harness, so the revisits are left out.
+fn reachable_complexity(node: &CallTreeNode) -> usize {
+    if node.revisit {
+        return 0;
+    }
+    let complexity = node.function.map_or(0, |function| function.complexity);
+    complexity + node.children.iter().map(reachable_complexity).sum::<usize>()
+}
+
+// Output the call tree in .data format following LLVM approach. Revisited functions are
+// left out as each function is only listed once in the .data call tree. Extended fields
+// enabled in the config are appended to the end of each line.
+fn write_call_tree_data(node: &CallTreeNode, depth: usize, config: &CallTreeConfig, result: &mut String) {
+    if node.revisit || config.overview_depth.is_some_and(|levels| depth >= levels) {
+        return;
+    }
+
+    let indent = config.indent.as_deref().unwrap_or(DEFAULT_INDENT).repeat(depth + 1);
+    result.push_str(&format!(
+        "{}{} {} linenumber={}",
+        indent, output_name(&node.name, config).replace(" ", ""), node.call_path, node.line_number
+    ));
+    if let Some(function_info) = node.function.filter(|_| config.emit_node_ids) {
+        result.push_str(&format!(" id={}", function_info.id()));
+    }
+    if let Some(written_name) = node.written_name.as_ref().filter(|_| config.emit_written_names) {
+        result.push_str(&format!(" written={}", written_name));
+    }
+    if let Some(arg_count) = node.arg_count.filter(|_| config.emit_arg_counts) {
+        result.push_str(&format!(" args={}", arg_count));
+    }
+    if let Some(function_info) = node.function.filter(|_| config.emit_visibility) {
+        result.push_str(&format!(" vis={}", function_info.visibility));
+    }
+    if config.emit_depths {
+        result.push_str(&format!(" depth={}", depth + 1));
+    }
+    if config.overview_depth == Some(depth + 1) {
+        let omitted: usize = node.children.iter().map(count_call_tree_lines).sum();
+        if omitted > 0 {
+            result.push_str(&format!(" omitted={}", omitted));
+        }
+    }
+    if is_sink(&node.name, config) {
+        result.push_str(" SINK");
+    }
+    result.push('\n');
+
+    for child in &node.children {
+        write_call_tree_data(child, depth + 1, config, result);
+    }
+}
+
+// Output the call trees of a harness as a GraphViz DOT graph where every function is a node
+// and every call is an edge labelled with its line number. Calls to functions that are
+// already expanded elsewhere are drawn as dashed back edges.
+pub fn call_tree_to_dot(harness_name: &str, roots: &[CallTreeNode], config: &CallTreeConfig) -> String {
+    let mut result = format!("digraph \"{}\" {{\n", escape_dot(harness_name));
+    result.push_str("  \"fuzz_target\" [shape=box];\n");
+    for root in roots {
+        write_dot_edges("fuzz_target", root, config, &mut result);
+    }
+    result.push_str("}\n");
+    result
+}
+
+// Recursively output the DOT edge to a node and the edges of its children
+fn write_dot_edges(parent: &str, node: &CallTreeNode, config: &CallTreeConfig, result: &mut String) {
+    let name = output_name(&node.name, config);
+    let style = if node.revisit { ", style=dashed" } else { "" };
+    result.push_str(&format!(
+        "  \"{}\" -> \"{}\" [label=\"{}\"{}];\n",
+        escape_dot(parent), escape_dot(&name), node.line_number, style
+    ));
+
+    for child in &node.children {
+        write_dot_edges(&name, child, config, result);
+    }
+}
+
+// Output the call trees of a harness as newline delimited JSON, one node per line in depth
+// first order. Nodes are numbered in output order and refer to their parent by number, which
+// is null for the roots at depth 0. Revisited functions are kept and marked as such.
+pub fn call_tree_to_ndjson(roots: &[CallTreeNode], config: &CallTreeConfig) -> String {
+    let mut result = String::new();
+    let mut next_id = 0;
+    let mut source_lines = SourceLineCache::default();
+    for root in roots {
+        write_ndjson_node(root, None, 0, &mut next_id, config, &mut source_lines, &mut result);
+    }
+    result
+}
+
+// Recursively output the JSON line of a node followed by the lines of its children
+fn write_ndjson_node(
+    node: &CallTreeNode,
+    parent: Option<usize>,
+    depth: usize,
+    next_id: &mut usize,
+    config: &CallTreeConfig,
+    source_lines: &mut SourceLineCache,
+    result: &mut String,
+) {
+    let id = *next_id;
+    *next_id += 1;
+    let mut line = serde_json::json!({
+        "id": id,
+        "parent": parent,
+        "name": output_name(&node.name, config),
+        "file": node.call_path,
+        "line": node.line_number,
+        "depth": depth,
+        "revisit": node.revisit,
+    });
+    if config.emit_snippets {
+        line["snippet"] = serde_json::json!(source_lines.line(&node.call_path, node.line_number));
+    }
+    result.push_str(&line.to_string());
+    result.push('\n');
+
+    for child in &node.children {
+        write_ndjson_node(child, Some(id), depth + 1, next_id, config, source_lines, result);
+    }
+}
+
+// Source lines of the files containing call sites, each file being read at most once
+#[derive(Default)]
+struct SourceLineCache {
+    files: HashMap<String, Option<Vec<String>>>,
+}
+
+impl SourceLineCache {
+    // Retrieve the trimmed source line at a 1-based line number, or None when the file cannot
+    // be read or the line is unknown or out of range
+    fn line(&mut self, path: &str, line_number: i32) -> Option<&str> {
+        let index = usize::try_from(line_number).ok()?.checked_sub(1)?;
+        if !self.files.contains_key(path) {
+            let lines = fs::read_to_string(path)
+                .ok()
+                .map(|content| content.lines().map(str::to_string).collect());
+            self.files.insert(path.to_string(), lines);
+        }
+        self.files[path].as_ref()?.get(index).map(|line| line.trim())
+    }
+}
+
+// Output the call trees of a harness as a self-contained HTML page. Calls with children are
+// collapsible, resolved functions link to their definition as file#Lline, and every call
+// lists its call site. Calls to functions already expanded elsewhere are marked as revisits.
+pub fn call_tree_to_html(harness_name: &str, roots: &[CallTreeNode], config: &CallTreeConfig) -> String {
+    let title = escape_html(harness_name);
+    let mut result = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>ul {{ list-style: none; }} .site, .revisit {{ color: grey; }}</style>\n\
+         </head>\n<body>\n<h1>{}</h1>\n<ul>\n",
+        title, title
+    );
+    for root in roots {
+        write_html_node(root, config, &mut result);
+    }
+    result.push_str("</ul>\n</body>\n</html>\n");
+    result
+}
+
+// Recursively output the list item of a node and the nested list of its children
+fn write_html_node(node: &CallTreeNode, config: &CallTreeConfig, result: &mut String) {
+    let name = escape_html(&output_name(&node.name, config));
+    let label = match node.function {
+        Some(function_info) => format!(
+            "<a href=\"{}#L{}\">{}</a>",
+            escape_html(&function_info.file), function_info.start_line, name
+        ),
+        None => name,
+    };
+    let site = format!(
+        "<span class=\"site\">{}:{}</span>",
+        escape_html(&node.call_path), node.line_number
+    );
+
+    if node.revisit {
+        result.push_str(&format!("<li>{} {} <span class=\"revisit\">(revisit)</span></li>\n", label, site));
+    } else if node.children.is_empty() {
+        result.push_str(&format!("<li>{} {}</li>\n", label, site));
+    } else {
+        result.push_str(&format!("<li><details open><summary>{} {}</summary>\n<ul>\n", label, site));
+        for child in &node.children {
+            write_html_node(child, config, result);
+        }
+        result.push_str("</ul>\n</details></li>\n");
+    }
+}
+
+// Escape a name or path for use as HTML text or attribute value
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Apply the configured name rewriter to a node name for output
+fn output_name(name: &str, config: &CallTreeConfig) -> String {
+    match &config.name_rewriter {
+        Some(rewrite) => rewrite(name),
+        None => name.to_string(),
+    }
+}
+
+// Escape a name for use as a quoted DOT identifier
+fn escape_dot(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Check if a node name matches one of the configured sink functions
+fn is_sink(name: &str, config: &CallTreeConfig) -> bool {
+    matches_configured_name(name, &config.sink_functions)
+}
+
+// Check if a name matches one of the configured function names, either exactly or by its
+// trailing path segments so that `from_raw` matches `Box::from_raw`
+fn matches_configured_name(name: &str, configured: &[String]) -> bool {
+    configured.iter().any(|configured| {
+        name == configured
+            || name
+                .strip_suffix(configured.as_str())
+                .is_some_and(|prefix| prefix.ends_with("::"))
+    })
+}
+
+// Check if a resolved function is defined in one of the configured dependency directories
+fn is_external_function(function_info: &FunctionInfo, config: &CallTreeConfig) -> bool {
+    config
+        .external_dirs
+        .iter()
+        .any(|dir| Path::new(&function_info.file).starts_with(dir))
+}
+
+// Check if a call that is not resolved to a project function should be kept in the call tree
+fn is_allowed_external(function_name: &str, config: &CallTreeConfig) -> bool {
+    match &config.crate_allowlist {
+        Some(prefixes) => prefixes.iter().any(|prefix| function_name.starts_with(prefix.as_str())),
+        None => true,
+    }
+}
+
+// Search for the functions in the analysis result and exclude functions/methods not from the project.
+// Empty or whitespace only names never match, as they would match any key by suffix.
+pub(crate) fn find_function<'a>(
+    function_name: &str,
+    function_map: &HashMap<String, &'a FunctionInfo>,
+) -> Option<&'a FunctionInfo> {
+    if function_name.trim().is_empty() {
+        return None;
+    }
+
+    // Exact match, test functions are only returned when named explicitly
+    if let Some(func) = function_map.get(function_name) {
+        return Some(*func);
+    }
+
+    // Match any key that ends with function_name, skipping test functions
+    if let Some((_, func)) = function_map
+        .iter()
+        .find(|(key, func)| !func.is_test && key.ends_with(function_name))
+    {
+        return Some(*func);
+    }
+
+    // Split and check segments from the right side, skipping test functions
+    let segments: Vec<&str> = function_name.split("::").collect();
+    for i in 0..segments.len() {
+        let partial_name = segments[i..].join("::");
+        if let Some(func) = function_map.get(&partial_name).filter(|func| !func.is_test) {
+            return Some(*func);
+        }
+    }
+
+    // No match found
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyse::analyse_directory;
+
+    // Write the files of a test project under a fresh directory of the system temp directory
+    fn write_files(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rust_function_analyser-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, content) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    // Analyse a project made of the given library source and extract the calls of a harness
+    fn harness_calls(name: &str, lib: &str, harness: &str, config: &CallTreeConfig) -> HarnessCalls {
+        let dir = write_files(name, &[("src/lib.rs", lib), ("fuzz/harness.rs", harness)]);
+        let functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        extract_called_functions(dir.join("fuzz/harness.rs").to_str().unwrap(), &functions, config).unwrap()
+    }
+
+    // Build the call trees of a harness and render them, with the paths in the rendered output
+    // relative to the test project directory
+    fn render_call_trees(
+        name: &str,
+        lib: &str,
+        harness: &str,
+        config: &CallTreeConfig,
+        render: impl Fn(&[CallTreeNode]) -> String,
+    ) -> String {
+        let dir = write_files(name, &[("src/lib.rs", lib), ("fuzz/harness.rs", harness)]);
+        let functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        let fuzz_file = dir.join("fuzz/harness.rs").to_string_lossy().to_string();
+        let calls = extract_called_functions(&fuzz_file, &functions, config).unwrap();
+        let function_map: HashMap<String, &FunctionInfo> = functions.iter().map(|f| (f.name.clone(), f)).collect();
+        let mut visited = VisitedSet::new(config.visited_capacity);
+        let roots = build_harness_call_trees(&fuzz_file, &calls, &function_map, &mut visited, config);
+        render(&roots).replace(&format!("{}/", dir.display()), "")
+    }
+
+    // Build the call trees of a harness and render them as .data call tree lines
+    fn call_tree_lines(name: &str, lib: &str, harness: &str, config: &CallTreeConfig) -> String {
+        render_call_trees(name, lib, harness, config, |roots| {
+            let mut data = String::new();
+            for root in roots {
+                write_call_tree_data(root, 0, config, &mut data);
+            }
+            data
+        })
+    }
+
+    // Run with the output directory of the call tree files set to the given directory. Tests
+    // writing output files hold a lock as the working directory is shared by all of them.
+    fn in_output_dir<T>(dir: &Path, run: impl FnOnce() -> T) -> T {
+        static OUTPUT_DIR: std::sync::Mutex<()> = std::sync::Mutex::new(());
+        let _guard = OUTPUT_DIR.lock().unwrap_or_else(|err| err.into_inner());
+        let previous_dir = std::env::current_dir().unwrap();
+        std::env::set_current_dir(dir).unwrap();
+        let result = run();
+        std::env::set_current_dir(previous_dir).unwrap();
+        result
+    }
+
+    fn has_call(calls: &HarnessCalls, name: &str, line: i32) -> bool {
+        calls.called_functions.contains(&(name.to_string(), line))
+    }
+
+    const PARSER_LIB: &str = "pub struct Parser;
+impl Parser {
+    pub fn new() -> Parser { Parser }
+    pub fn run(&self, data: &[u8]) {}
+    pub fn parse(data: &[u8]) -> Parser { Parser }
+}
+";
+
+    #[test]
+    fn local_macro_in_helper_does_not_set_entry_line() {
+        let harness = "macro_rules! run {
+    ($d:expr) => { Parser::parse($d) };
+}
+fn helper(d: &[u8]) {
+    run!(d);
+}
+fuzz_target!(|data: &[u8]| { helper(data); });
+";
+        let calls = harness_calls("local_macro_entry", PARSER_LIB, harness, &CallTreeConfig::default());
+        assert_eq!(calls.entry_line, Some(7));
+        assert!(has_call(&calls, "Parser::parse", 5));
+        assert!(has_call(&calls, "helper", 7));
+    }
+
+    #[test]
+    fn transparent_wrappers_only_skip_standard_functions() {
+        let lib = format!("{}pub fn identity(data: &[u8]) -> &[u8] {{ data }}\n", PARSER_LIB);
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let p = std::hint::black_box(Parser::parse(data));
+    identity(data);
+    std::convert::identity(data);
+    mem::drop(p);
+    other::drop(data);
+});
+";
+        let calls = harness_calls("transparent_wrappers", &lib, harness, &CallTreeConfig::default());
+        assert!(has_call(&calls, "Parser::parse", 2));
+        assert!(has_call(&calls, "identity", 3));
+        assert!(has_call(&calls, "other::drop", 6));
+        let names: Vec<&str> = calls.called_functions.iter().map(|(name, _)| name.as_str()).collect();
+        assert!(!names.contains(&"std::hint::black_box"));
+        assert!(!names.contains(&"std::convert::identity"));
+        assert!(!names.contains(&"mem::drop"));
+    }
+
+    #[test]
+    fn bare_harness_path_prefers_harness_functions() {
+        let harness = "fuzz_target!(run);
+fn run(data: &[u8]) {
+    let p = Parser::parse(data);
+}
+";
+        let config = CallTreeConfig { statement_roots: true, ..Default::default() };
+        let calls = harness_calls("bare_path_local", PARSER_LIB, harness, &config);
+        assert_eq!(calls.called_functions, vec![("Parser::parse".to_string(), 3)]);
+
+        // Without a harness function, a project method of the same name is not the harness
+        let calls = harness_calls("bare_path_method", PARSER_LIB, "fuzz_target!(run);\n", &config);
+        assert!(calls.called_functions.is_empty());
+
+        let lib = format!("{}pub fn run(data: &[u8]) {{}}\n", PARSER_LIB);
+        let calls = harness_calls("bare_path_project", &lib, "fuzz_target!(run);\n", &config);
+        assert_eq!(calls.called_functions, vec![("run".to_string(), 1)]);
+    }
+
+    #[test]
+    fn statement_roots_nest_enclosed_calls() {
+        let lib = "pub fn f(x: u8) -> u8 { g(x) }
+pub fn g(x: u8) -> u8 { x }
+pub fn h() {}
+";
+        let harness = "fuzz_target!(|data: &[u8]| {
+    f(g(data[0]));
+    h();
+});
+";
+        let config = CallTreeConfig { statement_roots: true, ..Default::default() };
+        let dir = write_files("statement_roots_nest", &[("src/lib.rs", lib), ("fuzz/harness.rs", harness)]);
+        let functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        let fuzz_file = dir.join("fuzz/harness.rs").to_string_lossy().to_string();
+        let calls = extract_called_functions(&fuzz_file, &functions, &config).unwrap();
+        assert_eq!(
+            calls.argument_parents.get(&("g".to_string(), 2)),
+            Some(&("f".to_string(), 2))
+        );
+
+        let function_map: HashMap<String, &FunctionInfo> = functions.iter().map(|f| (f.name.clone(), f)).collect();
+        let mut visited = VisitedSet::new(None);
+        let roots = build_harness_call_trees(&fuzz_file, &calls, &function_map, &mut visited, &config);
+        let root_names: Vec<&str> = roots.iter().map(|root| root.name.as_str()).collect();
+        assert_eq!(root_names, vec!["f", "h"]);
+        assert_eq!(roots[0].children[0].name, "g");
+        assert_eq!(roots[0].children[0].line_number, 2);
+    }
+
+    #[test]
+    fn generic_helpers_only_record_instantiated_calls() {
+        let harness = "fn drive<T: Run>(d: &[u8]) {
+    T::run(d);
+    Parser::parse(d);
+}
+fuzz_target!(|data: &[u8]| {
+    drive::<Parser>(data);
+});
+";
+        let calls = harness_calls("generic_helpers", PARSER_LIB, harness, &CallTreeConfig::default());
+        assert!(has_call(&calls, "Parser::run", 6));
+        assert!(has_call(&calls, "Parser::parse", 3));
+        assert!(calls.called_functions.iter().all(|(name, _)| !name.starts_with("T::")));
+    }
+
+    #[test]
+    fn assignments_in_blocks_update_outer_variables() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let mut p = None;
+    {
+        p = Some(Parser::new());
+    }
+    p.unwrap().run(data);
+    let q = Parser::new();
+    {
+        let q = data.len();
+    }
+    q.run(data);
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("block_assignments", PARSER_LIB, harness, &config);
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 6)));
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 11)));
+    }
+
+    #[test]
+    fn call_tree_buffer_is_reused_between_harnesses() {
+        let lib = "pub fn a() { b(); }
+pub fn b() { c(); }
+pub fn c() {}
+";
+        let dir = write_files("buffer_reuse", &[("src/lib.rs", lib)]);
+        let functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        let config = CallTreeConfig::default();
+        let generator = HarnessGenerator::new(&functions, &config);
+        let tree = |name: &str| {
+            let mut visited = VisitedSet::new(None);
+            build_call_tree(name, &generator.function_map, "harness.rs", 1, &mut visited, 0, &config).unwrap()
+        };
+
+        let mut call_tree_data = String::new();
+        generator.write_call_tree_buffer(&[tree("a")], &mut call_tree_data);
+        assert_eq!(call_tree_data.lines().count(), 3);
+        let capacity = call_tree_data.capacity();
+        assert!(capacity < MAX_RESERVED_BYTES);
+
+        // A smaller tree replaces the previous lines without growing the buffer
+        generator.write_call_tree_buffer(&[tree("c")], &mut call_tree_data);
+        assert_eq!(call_tree_data.lines().count(), 1);
+        assert!(call_tree_data.starts_with("  c "));
+        assert_eq!(call_tree_data.capacity(), capacity);
+    }
+
+    #[test]
+    fn harness_stats_are_populated() {
+        let harness = "fuzz_target!(|data: &[u8]| { Parser::parse(data).run(data); });\n";
+        let dir = write_files("harness_stats", &[("src/lib.rs", PARSER_LIB), ("fuzz/harness.rs", harness)]);
+        let functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        let fuzz_file = dir.join("fuzz/harness.rs").to_string_lossy().to_string();
+        let config = CallTreeConfig::default();
+        let generator = HarnessGenerator::new(&functions, &config);
+        let generated = in_output_dir(&dir, || generator.generate(&fuzz_file, &mut String::new()))
+            .unwrap()
+            .unwrap();
+
+        let stats = &generated.stats;
+        assert!(stats.parse_time > Duration::ZERO);
+        assert!(stats.walk_time > Duration::ZERO);
+        assert!(stats.build_time > Duration::ZERO);
+        assert!(stats.visited_size > 0);
+        let data = fs::read_to_string(dir.join("fuzzerLogFile-harness.data")).unwrap();
+        let header = format!("Call tree\nfuzz_target {} linenumber=1\n", fuzz_file);
+        assert_eq!(data.len(), header.len() + stats.output_size);
+        assert_eq!(data.lines().count(), 4);
+    }
+
+    #[test]
+    fn registered_macro_patterns_expand_at_the_invocation_line() {
+        let mut config = CallTreeConfig::default();
+        config.macro_patterns.insert("my_fuzz".to_string(), "$1::$2(data)".to_string());
+        let harness = "fuzz_target!(|data: &[u8]| {
+    my_fuzz!(Parser, parse);
+});
+";
+        let calls = harness_calls("macro_patterns", PARSER_LIB, harness, &config);
+        assert_eq!(calls.called_functions, vec![("Parser::parse".to_string(), 2)]);
+    }
+
+    #[test]
+    fn entry_line_is_the_fuzz_target_line() {
+        let harness = "use lib::Parser;
+
+fuzz_target!(|data: &[u8]| {
+    Parser::parse(data);
+});
+";
+        let calls = harness_calls("entry_line", PARSER_LIB, harness, &CallTreeConfig::default());
+        assert_eq!(calls.entry_line, Some(3));
+
+        let calls = harness_calls("no_entry_line", PARSER_LIB, "fn main() {}\n", &CallTreeConfig::default());
+        assert_eq!(calls.entry_line, None);
+    }
+
+    #[test]
+    fn include_macros_are_followed_at_the_include_line() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    include!(\"body.rs\");
+});
+";
+        let dir = write_files(
+            "include_macro",
+            &[
+                ("src/lib.rs", PARSER_LIB),
+                ("fuzz/harness.rs", harness),
+                ("fuzz/body.rs", "Parser::parse(data).run(data)"),
+            ],
+        );
+        let functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        let fuzz_file = dir.join("fuzz/harness.rs");
+        let calls = extract_called_functions(fuzz_file.to_str().unwrap(), &functions, &CallTreeConfig::default()).unwrap();
+        assert!(has_call(&calls, "Parser::parse", 2));
+        assert!(has_call(&calls, "Parser::run", 2));
+    }
+
+    #[test]
+    fn harness_function_infos_are_built_without_output_files() {
+        let harness = "fuzz_target!(|data: &[u8]| { Parser::parse(data); });\n";
+        let dir = write_files("harness_function_infos", &[("src/lib.rs", PARSER_LIB), ("fuzz/harness.rs", harness)]);
+        let functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        let config = CallTreeConfig::default();
+        let harnesses = in_output_dir(&dir, || {
+            build_harness_function_infos(dir.to_str().unwrap(), &functions, &config).unwrap()
+        });
+
+        assert_eq!(harnesses.len(), 1);
+        let (fuzz_file, harness) = harnesses.iter().next().unwrap();
+        assert!(fuzz_file.ends_with("harness.rs"));
+        assert_eq!(harness.name, "fuzz_target");
+        assert_eq!(harness.called_functions, vec!["Parser::parse"]);
+        assert!(!dir.join("fuzzerLogFile-harness.data").exists());
+    }
+
+    const BUILDER_LIB: &str = "pub struct Builder;
+pub struct Engine;
+impl Builder {
+    pub fn new() -> Builder { Builder }
+    pub fn width(self, w: usize) -> Builder { self }
+    pub fn height(self, h: usize) -> Builder { self }
+    pub fn build(self) -> Engine { Engine }
+}
+impl Engine {
+    pub fn run(&self, data: &[u8]) {}
+}
+";
+
+    #[test]
+    fn method_chains_are_typed_link_by_link() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    Builder::new().width(1).height(2).build().run(data);
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("method_chain", BUILDER_LIB, harness, &config);
+        let names: Vec<&str> = calls.raw_called_functions.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["Builder::new", "Builder::width", "Builder::height", "Builder::build", "Engine::run"]
+        );
+    }
+
+    // Resolver typing the `handle` variable as a Builder, counting how often receivers fall
+    // back to it
+    struct CountingResolver(std::sync::Arc<AtomicUsize>);
+
+    impl crate::config::ReceiverResolver for CountingResolver {
+        fn resolve_type(&self, expr: &Expr, _type_of: &dyn Fn(&Expr) -> Option<String>) -> Option<String> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            match expr {
+                Expr::Path(path_expr) if path_expr.path.is_ident("handle") => Some("Builder".to_string()),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn long_method_chains_are_typed_in_linear_work() {
+        // Type a chain of the given number of links, one per line, returning the number of
+        // receivers typed through the resolver
+        let typed_chain = |links: usize| {
+            let mut harness = "fuzz_target!(|data: &[u8]| {\n    let handle = open(data);\n    handle\n".to_string();
+            for link in 0..links {
+                harness.push_str(if link % 2 == 0 { "        .width(1)\n" } else { "        .height(2)\n" });
+            }
+            harness.push_str("        .build()\n        .run(data);\n});\n");
+
+            let resolved = std::sync::Arc::new(AtomicUsize::new(0));
+            let config = CallTreeConfig {
+                keep_raw_calls: true,
+                receiver_resolvers: vec![Box::new(CountingResolver(resolved.clone()))],
+                ..Default::default()
+            };
+            let calls = harness_calls(&format!("long_method_chain_{}", links), BUILDER_LIB, &harness, &config);
+            for link in 0..links {
+                let name = if link % 2 == 0 { "Builder::width" } else { "Builder::height" };
+                assert!(calls.raw_called_functions.contains(&(name.to_string(), 4 + link as i32)), "link {}", link);
+            }
+            assert!(calls.raw_called_functions.contains(&("Builder::build".to_string(), 4 + links as i32)));
+            assert!(calls.raw_called_functions.contains(&("Engine::run".to_string(), 5 + links as i32)));
+            resolved.load(Ordering::SeqCst)
+        };
+
+        // The receiver at the base of the chain is typed once however long the chain is, instead
+        // of once more for every link
+        let short = typed_chain(300);
+        assert!(short > 0);
+        assert_eq!(typed_chain(600), short);
+    }
+
+    #[test]
+    fn crate_allowlist_filters_calls_outside_the_project() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    Parser::parse(data);
+    mylib::decode(data);
+    other::decode(data);
+});
+";
+        let config = CallTreeConfig { crate_allowlist: Some(vec!["mylib::".to_string()]), ..Default::default() };
+        let data = call_tree_lines("crate_allowlist", PARSER_LIB, harness, &config);
+        assert_eq!(
+            data,
+            "  Parser::parse fuzz/harness.rs linenumber=2\n  mylib::decode fuzz/harness.rs linenumber=3\n"
+        );
+
+        let config = CallTreeConfig { crate_allowlist: Some(Vec::new()), ..Default::default() };
+        let data = call_tree_lines("empty_crate_allowlist", PARSER_LIB, harness, &config);
+        assert_eq!(data, "  Parser::parse fuzz/harness.rs linenumber=2\n");
+    }
+
+    #[test]
+    fn block_statements_go_through_the_typed_visitor() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let p = Parser::parse(data);
+    if data.len() > 1 {
+        p.run(data);
+    }
+    let Some(first) = data.first() else { return; };
+    assert_eq!(Parser::new().run(data), ());
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("typed_statements", PARSER_LIB, harness, &config);
+        let raw = &calls.raw_called_functions;
+        assert!(raw.contains(&("Parser::run".to_string(), 4)));
+        assert!(raw.contains(&("Parser::new".to_string(), 7)));
+        assert!(raw.contains(&("Parser::run".to_string(), 7)));
+    }
+
+    #[test]
+    fn unknown_call_lines_are_recorded_as_minus_one() {
+        assert_eq!(normalise_line(0), -1);
+        assert_eq!(normalise_line(12), 12);
+
+        let lib = format!("{}pub fn check(data: &[u8]) {{ Parser::new(); }}\n", PARSER_LIB);
+        let mut config = CallTreeConfig::default();
+        config.macro_patterns.insert("my_fuzz".to_string(), "$1::$2(data)".to_string());
+        let harness = "fuzz_target!(|data: &[u8]| {
+    my_fuzz!(Parser, parse);
+    check(data);
+});
+";
+        let data = call_tree_lines("no_zero_lines", &lib, harness, &config);
+        assert!(!data.contains("linenumber=0"));
+        assert_eq!(
+            data,
+            "  Parser::parse fuzz/harness.rs linenumber=2\n  check fuzz/harness.rs linenumber=3\n    Parser::new src/lib.rs linenumber=7\n"
+        );
+    }
+
+    const CALL_CHAIN_LIB: &str = "pub fn a(data: &[u8]) { b(data); c(data); }
+pub fn b(data: &[u8]) { c(data); }
+pub fn c(data: &[u8]) {}
+";
+
+    #[test]
+    fn dot_output_draws_revisits_as_dashed_edges() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    a(data);
+});
+";
+        let config = CallTreeConfig::default();
+        let dot = render_call_trees("dot_output", CALL_CHAIN_LIB, harness, &config, |roots| {
+            call_tree_to_dot("fuzz-harness", roots, &config)
+        });
+        let mut lines = dot.lines();
+        assert_eq!(lines.next(), Some("digraph \"fuzz-harness\" {"));
+        assert_eq!(lines.next(), Some("  \"fuzz_target\" [shape=box];"));
+        assert_eq!(lines.next(), Some("  \"fuzz_target\" -> \"a\" [label=\"2\"];"));
+        assert_eq!(lines.next(), Some("  \"a\" -> \"b\" [label=\"1\"];"));
+        assert_eq!(lines.next(), Some("  \"b\" -> \"c\" [label=\"2\"];"));
+        assert_eq!(lines.next(), Some("  \"a\" -> \"c\" [label=\"1\", style=dashed];"));
+        assert_eq!(lines.next(), Some("}"));
+
+        // The .data output leaves the revisit out
+        let data = call_tree_lines("dot_data_output", CALL_CHAIN_LIB, harness, &config);
+        assert_eq!(data.lines().count(), 3);
+    }
+
+    #[test]
+    fn qualified_trait_calls_resolve_to_the_concrete_type() {
+        let lib = format!("{}pub trait Decode {{ fn decode(data: &[u8]) -> Self; }}
+impl Decode for Parser {{ fn decode(data: &[u8]) -> Parser {{ Parser }} }}
+", PARSER_LIB);
+        let harness = "fuzz_target!(|data: &[u8]| {
+    <Parser as Decode>::decode(data).run(data);
+});
+";
+        let calls = harness_calls("qualified_trait_call", &lib, harness, &CallTreeConfig::default());
+        assert!(has_call(&calls, "Parser::decode", 2));
+        assert!(has_call(&calls, "Parser::run", 2));
+    }
+
+    #[test]
+    fn harness_depth_is_the_depth_of_its_call_tree() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    a(data);
+    c(data);
+});
+";
+        let dir = write_files("harness_depth", &[("src/lib.rs", CALL_CHAIN_LIB), ("fuzz/harness.rs", harness)]);
+        let functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        let harnesses = build_harness_function_infos(dir.to_str().unwrap(), &functions, &CallTreeConfig::default()).unwrap();
+        let harness = harnesses.values().next().unwrap();
+        assert_eq!(harness.depth, 3);
+    }
+
+    #[test]
+    fn receivers_resolve_through_try_and_getters() {
+        let lib = format!("{}pub struct Error;
+pub struct Config {{ parser: Parser }}
+pub fn load(data: &[u8]) -> Result<Parser, Error> {{ Ok(Parser) }}
+impl Config {{
+    pub fn new() -> Self {{ Config {{ parser: Parser }} }}
+    pub fn parser(&self) -> &Parser {{ &self.parser }}
+}}
+", PARSER_LIB);
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let p = load(data)?;
+    p.run(data);
+    Config::new().parser().run(data);
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("try_and_getters", &lib, harness, &config);
+        let raw = &calls.raw_called_functions;
+        assert!(raw.contains(&("Parser::run".to_string(), 3)));
+        assert!(raw.contains(&("Config::parser".to_string(), 4)));
+        assert!(raw.contains(&("Parser::run".to_string(), 4)));
+    }
+
+    #[test]
+    fn node_ids_are_appended_when_configured() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    b(data);
+    unknown(data);
+});
+";
+        let config = CallTreeConfig { emit_node_ids: true, ..Default::default() };
+        let data = call_tree_lines("node_ids", CALL_CHAIN_LIB, harness, &config);
+        assert_eq!(
+            data,
+            "  b fuzz/harness.rs linenumber=2 id=src/lib.rs:2:b
+    c src/lib.rs linenumber=2 id=src/lib.rs:3:c
+  unknown fuzz/harness.rs linenumber=3
+"
+        );
+    }
+
+    #[test]
+    fn typed_closure_parameters_type_their_calls() {
+        let harness = "fuzz_target!(|parser: Parser| {
+    parser.run(&[]);
+});
+";
+        let calls = harness_calls("typed_closure_params", PARSER_LIB, harness, &CallTreeConfig::default());
+        assert_eq!(calls.called_functions, vec![("Parser::run".to_string(), 2)]);
+    }
+
+    #[test]
+    fn typed_closure_parameters_reach_every_match_arm() {
+        let lib = format!(
+            "{}pub enum Operation {{ Parse(Vec<u8>), Run(Vec<u8>), Reset }}
+impl Operation {{
+    pub fn reset(&self) {{}}
+}}
+",
+            PARSER_LIB
+        );
+        let harness = "fuzz_target!(|op: Operation| match &op {
+    Operation::Parse(data) => {
+        Parser::parse(data);
+    }
+    Operation::Run(data) => Parser::new().run(data),
+    Operation::Reset => op.reset(),
+});
+";
+        let lines = call_tree_lines("typed_closure_match", &lib, harness, &CallTreeConfig::default());
+        assert!(lines.contains("  Parser::parse fuzz/harness.rs linenumber=3\n"));
+        assert!(lines.contains("  Parser::run fuzz/harness.rs linenumber=5\n"));
+        assert!(lines.contains("  Operation::reset fuzz/harness.rs linenumber=6\n"));
+    }
+
+    #[test]
+    fn fuzzy_lookups_skip_test_functions() {
+        let lib = "pub fn check_input() {}
+#[test]
+fn input() {}
+";
+        let dir = write_files("fuzzy_test_lookup", &[("src/lib.rs", lib)]);
+        let functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        let function_map: HashMap<String, &FunctionInfo> = functions.iter().map(|f| (f.name.clone(), f)).collect();
+        assert_eq!(find_function("input", &function_map).map(|f| f.name.as_str()), Some("input"));
+        assert_eq!(find_function("tests::input", &function_map).map(|f| f.name.as_str()), None);
+        assert_eq!(find_function("_input", &function_map).map(|f| f.name.as_str()), Some("check_input"));
+    }
+
+    const LOAD_LIB: &str = "pub struct Error;
+pub fn load(data: &[u8]) -> Result<Parser, Error> { Ok(Parser) }
+pub fn find(data: &[u8]) -> Option<Parser> { None }
+";
+
+    #[test]
+    fn if_let_bindings_are_typed_and_guards_visited() {
+        let lib = format!("{}{}", PARSER_LIB, LOAD_LIB);
+        let harness = "fuzz_target!(|data: &[u8]| {
+    if let Ok(p) = load(data) {
+        p.run(data);
+    }
+    match data.len() {
+        0 if find(data).is_some() => {}
+        _ => {}
+    }
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("if_let_bindings", &lib, harness, &config);
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 3)));
+        assert!(calls.raw_called_functions.contains(&("find".to_string(), 6)));
+    }
+
+    #[test]
+    fn every_branch_of_an_if_chain_is_visited() {
+        let lib = "pub fn small(data: &[u8]) {}
+pub fn large(data: &[u8]) {}
+pub fn empty() {}
+pub fn a() -> Option<u8> { None }
+pub fn b() -> bool { true }
+pub fn use_value(x: u8) {}
+";
+        let harness = "fuzz_target!(|data: &[u8]| {
+    if data.len() > 16 {
+        large(data);
+    } else if !data.is_empty() {
+        small(data);
+    } else {
+        empty();
+    }
+    if let Some(x) = a() && b() {
+        use_value(x);
+    }
+});
+";
+        let calls = harness_calls("if_chain_branches", lib, harness, &CallTreeConfig::default());
+        assert!(has_call(&calls, "large", 3));
+        assert!(has_call(&calls, "small", 5));
+        assert!(has_call(&calls, "empty", 7));
+        assert!(has_call(&calls, "a", 9));
+        assert!(has_call(&calls, "b", 9));
+        assert!(has_call(&calls, "use_value", 10));
+    }
+
+    #[test]
+    fn unparsable_macro_bodies_are_scanned_when_configured() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    custom!(data => Parser::parse(data); if ready(data) { go(data) });
+});
+";
+        let calls = harness_calls("scan_disabled", PARSER_LIB, harness, &CallTreeConfig::default());
+        assert!(calls.called_functions.is_empty());
+
+        let config = CallTreeConfig { scan_macro_tokens: true, ..Default::default() };
+        let calls = harness_calls("scan_enabled", PARSER_LIB, harness, &config);
+        let mut heuristic_calls = calls.heuristic_calls.clone();
+        heuristic_calls.sort();
+        assert_eq!(
+            heuristic_calls,
+            vec![("Parser::parse".to_string(), 2), ("go".to_string(), 2), ("ready".to_string(), 2)]
+        );
+    }
+
+    #[test]
+    fn assignments_update_variable_types() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let mut p;
+    p = Parser::parse(data);
+    p.run(data);
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("assignment_types", PARSER_LIB, harness, &config);
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 4)));
+    }
+
+    #[test]
+    fn mismatched_arity_resolutions_are_rejected_when_configured() {
+        let lib = format!("{}{}", PARSER_LIB, LOAD_LIB);
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let p = load(data);
+    load(data, 1);
+    Parser::run(&p, data);
+});
+";
+        let config = CallTreeConfig { check_call_arity: true, ..Default::default() };
+        let calls = harness_calls("call_arity", &lib, harness, &config);
+        let mismatches: Vec<&(String, i32)> = calls.arity_mismatches.iter().collect();
+        assert_eq!(mismatches, vec![&("load".to_string(), 3)]);
+        let data = call_tree_lines("call_arity_tree", &lib, harness, &config);
+        assert!(data.contains("  load fuzz/harness.rs linenumber=3\n"));
+
+        let calls = harness_calls("call_arity_unchecked", &lib, harness, &CallTreeConfig::default());
+        assert!(calls.arity_mismatches.is_empty());
+    }
+
+    #[test]
+    fn source_expander_output_is_analysed_instead_of_the_file() {
+        let config = CallTreeConfig {
+            source_expander: Some(Box::new(|_: &str| {
+                Ok("fuzz_target!(|data: &[u8]| {\n    Parser::parse(data);\n});\n".to_string())
+            })),
+            ..Default::default()
+        };
+        let calls = harness_calls("source_expander", PARSER_LIB, "not rust at all", &config);
+        assert_eq!(calls.called_functions, vec![("Parser::parse".to_string(), 2)]);
+    }
+
+    #[test]
+    fn let_annotations_and_indexed_elements_are_typed() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let p: Parser = make(data);
+    p.run(data);
+    let parsers: Vec<Parser> = Vec::new();
+    parsers[0].run(data);
+    let more = data.chunks(2).map(Parser::parse).collect::<Vec<Parser>>();
+    more[1].run(data);
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("let_annotations", PARSER_LIB, harness, &config);
+        for line in [3, 5, 7] {
+            assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), line)));
+        }
+    }
+
+    #[test]
+    fn has_fuzz_harness_finds_nested_harnesses() {
+        let dir = write_files("has_fuzz_harness", &[
+            ("src/lib.rs", PARSER_LIB),
+            ("fuzz/fuzz_targets/parse.rs", "fuzz_target!(|data: &[u8]| {});\n"),
+        ]);
+        let config = CallTreeConfig::default();
+        assert!(has_fuzz_harness(dir.to_str().unwrap(), &config));
+        assert!(has_fuzz_harness(dir.join("fuzz").to_str().unwrap(), &config));
+        assert!(!has_fuzz_harness(dir.join("src").to_str().unwrap(), &config));
+        assert!(!has_fuzz_harness(dir.join("missing").to_str().unwrap(), &config));
+    }
+
+    #[test]
+    fn spawned_closures_capture_types_and_can_be_skipped() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let p = Parser::new();
+    std::thread::spawn(move || {
+        p.run(data);
+    });
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("spawned_closures", PARSER_LIB, harness, &config);
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 4)));
+
+        let config = CallTreeConfig { keep_raw_calls: true, skip_spawned_closures: true, ..Default::default() };
+        let calls = harness_calls("skipped_spawned_closures", PARSER_LIB, harness, &config);
+        assert!(calls.raw_called_functions.iter().all(|(name, _)| name != "Parser::run"));
+        assert!(calls.raw_called_functions.contains(&("Parser::new".to_string(), 2)));
+    }
+
+    #[test]
+    fn call_tree_indentation_is_configurable() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    b(data);
+});
+";
+        let config = CallTreeConfig { indent: Some("\t".to_string()), ..Default::default() };
+        let lines = call_tree_lines("configurable_indent", CALL_CHAIN_LIB, harness, &config);
+        assert_eq!(lines, "\tb fuzz/harness.rs linenumber=2\n\t\tc src/lib.rs linenumber=2\n");
+
+        let lines = call_tree_lines("default_indent", CALL_CHAIN_LIB, harness, &CallTreeConfig::default());
+        assert_eq!(lines, "  b fuzz/harness.rs linenumber=2\n    c src/lib.rs linenumber=2\n");
+    }
+
+    #[test]
+    fn clone_like_methods_keep_the_receiver_type() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let p = Parser::new().clone();
+    p.run(data);
+    Parser::parse(data).to_owned().run(data);
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("clone_like_methods", PARSER_LIB, harness, &config);
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 3)));
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 4)));
+    }
+
+    #[test]
+    fn harness_type_aliases_are_expanded() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let p = P::new();
+    p.run(data);
+    let q: P = P::parse(data);
+    q.run(data);
+});
+
+type P = Parser;
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("harness_type_aliases", PARSER_LIB, harness, &config);
+        let raw: Vec<(&str, i32)> = calls.raw_called_functions.iter().map(|(name, line)| (name.as_str(), *line)).collect();
+        assert_eq!(raw, vec![("Parser::new", 2), ("Parser::run", 3), ("Parser::parse", 4), ("Parser::run", 5)]);
+    }
+
+    #[test]
+    fn argument_calls_are_nested_when_configured() {
+        let lib = "pub fn f(x: u8) -> u8 { x }
+pub fn g(x: u8) -> u8 { x }
+pub fn h() {}
+";
+        let harness = "fuzz_target!(|data: &[u8]| {
+    f(g(data[0]));
+    h();
+});
+";
+        let lines = call_tree_lines("flat_argument_calls", lib, harness, &CallTreeConfig::default());
+        assert_eq!(
+            lines,
+            "  f fuzz/harness.rs linenumber=2\n  g fuzz/harness.rs linenumber=2\n  h fuzz/harness.rs linenumber=3\n"
+        );
+
+        let config = CallTreeConfig { nest_argument_calls: true, ..Default::default() };
+        let lines = call_tree_lines("nested_argument_calls", lib, harness, &config);
+        assert_eq!(
+            lines,
+            "  f fuzz/harness.rs linenumber=2\n    g fuzz/harness.rs linenumber=2\n  h fuzz/harness.rs linenumber=3\n"
+        );
+    }
+
+    #[test]
+    fn name_rewriter_applies_to_the_output_names() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    b(data);
+});
+";
+        let rewriter: crate::config::NameRewriter = Box::new(|name| format!("lib::{}", name));
+        let config = CallTreeConfig { name_rewriter: Some(rewriter), ..Default::default() };
+        let lines = call_tree_lines("name_rewriter", CALL_CHAIN_LIB, harness, &config);
+        assert_eq!(lines, "  lib::b fuzz/harness.rs linenumber=2\n    lib::c src/lib.rs linenumber=2\n");
+
+        let dot = render_call_trees("name_rewriter_dot", CALL_CHAIN_LIB, harness, &config, |roots| {
+            call_tree_to_dot("harness", roots, &config)
+        });
+        assert!(dot.contains("\"lib::b\" -> \"lib::c\""));
+    }
+
+    #[test]
+    fn into_turbofish_and_unwrap_calls_are_typed() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    data.to_vec().into::<Parser>().run(data);
+    let p = data.try_into::<Parser>().unwrap();
+    p.run(data);
+    load(data).expect(\"valid\").run(data);
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let lib = format!("{}{}", PARSER_LIB, LOAD_LIB);
+        let calls = harness_calls("into_turbofish", &lib, harness, &config);
+        for line in [2, 4, 5] {
+            assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), line)));
+        }
+    }
+
+    #[test]
+    fn harness_body_shapes_and_unparsable_harnesses() {
+        let config = CallTreeConfig::default();
+        for (name, harness) in [
+            ("attributed_closure", "fuzz_target!(#[cfg(fuzzing)] |data: &[u8]| { Parser::parse(data); });\n"),
+            ("bare_statements", "fuzz_target!(let data = b\"\"; Parser::parse(data););\n"),
+            ("labelled_args", "fuzz_target!(init: Parser::new(), |data: &[u8]| { Parser::parse(data); });\n"),
+        ] {
+            let calls = harness_calls(name, PARSER_LIB, harness, &config);
+            assert!(has_call(&calls, "Parser::parse", 1), "{}", name);
+        }
+
+        let dir = write_files("unparsable_harness", &[("src/lib.rs", PARSER_LIB), ("fuzz/harness.rs", "fn {")]);
+        let functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        let result = extract_called_functions(dir.join("fuzz/harness.rs").to_str().unwrap(), &functions, &config);
+        assert!(matches!(result, Err(err) if err.kind() == io::ErrorKind::InvalidData));
+    }
+
+    #[test]
+    fn harness_reached_functions_are_unique_and_sorted() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let p = Parser::parse(data);
+    p.run(data);
+    Parser::new().run(data);
+});
+";
+        let dir = write_files("unique_reached", &[("src/lib.rs", PARSER_LIB), ("fuzz/harness.rs", harness)]);
+        let functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        let harnesses = build_harness_function_infos(dir.to_str().unwrap(), &functions, &CallTreeConfig::default())
+            .unwrap();
+        let harness = harnesses.values().next().unwrap();
+        assert_eq!(harness.called_functions, vec!["Parser::new", "Parser::parse", "Parser::run"]);
+    }
+
+    #[test]
+    fn generic_return_types_are_pinned_by_the_turbofish() {
+        let lib = format!("{}pub fn decode<T>(data: &[u8]) -> Option<T> {{ None }}\n", PARSER_LIB);
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let p = decode::<Parser>(data).unwrap();
+    p.run(data);
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("turbofish_return_types", &lib, harness, &config);
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 3)));
+    }
+
+    #[test]
+    fn custom_entry_macros_mark_harnesses() {
+        let harness = "my_fuzz!(|data: &[u8]| {
+    Parser::parse(data);
+});
+";
+        let config = CallTreeConfig { entry_macros: vec!["my_fuzz".to_string()], ..Default::default() };
+        let dir = write_files("custom_entry_macros", &[("src/lib.rs", PARSER_LIB), ("fuzz/harness.rs", harness)]);
+        let functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        let harnesses = build_harness_function_infos(dir.to_str().unwrap(), &functions, &config).unwrap();
+        assert_eq!(harnesses.values().next().unwrap().called_functions, vec!["Parser::parse"]);
+
+        let calls = harness_calls("custom_entry_macro_line", PARSER_LIB, harness, &config);
+        assert_eq!(calls.entry_line, Some(1));
+        assert!(build_harness_function_infos(dir.to_str().unwrap(), &functions, &CallTreeConfig::default())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn range_bounds_are_visited() {
+        let lib = "pub fn start(data: &[u8]) -> usize { 0 }
+pub fn end(data: &[u8]) -> usize { data.len() }
+";
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let slice = &data[start(data)..end(data)];
+});
+";
+        let calls = harness_calls("range_bounds", lib, harness, &CallTreeConfig::default());
+        assert!(has_call(&calls, "start", 2));
+        assert!(has_call(&calls, "end", 2));
+    }
+
+    #[test]
+    fn harness_struct_literals_and_fields_are_typed() {
+        let harness = "struct Input {
+    parser: Parser,
+}
+
+struct Wrapped(Parser);
+
+fuzz_target!(|data: &[u8]| {
+    let input = Input { parser: Parser::new() };
+    input.parser.run(data);
+    let wrapped = Wrapped(Parser::new());
+    wrapped.0.run(data);
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("struct_fields", PARSER_LIB, harness, &config);
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 9)));
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 11)));
+    }
+
+    #[test]
+    fn overview_depth_cuts_the_call_tree() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    a(data);
+});
+";
+        let config = CallTreeConfig { overview_depth: Some(1), ..Default::default() };
+        let lines = call_tree_lines("overview_depth_one", CALL_CHAIN_LIB, harness, &config);
+        assert_eq!(lines, "  a fuzz/harness.rs linenumber=2 omitted=2\n");
+
+        let config = CallTreeConfig { overview_depth: Some(2), ..Default::default() };
+        let lines = call_tree_lines("overview_depth_two", CALL_CHAIN_LIB, harness, &config);
+        assert_eq!(lines, "  a fuzz/harness.rs linenumber=2\n    b src/lib.rs linenumber=1 omitted=1\n");
+    }
+
+    #[test]
+    fn try_chains_of_local_result_helpers_are_resolved() {
+        let lib = format!("{}{}", PARSER_LIB, LOAD_LIB);
+        let harness = "fn helper(data: &[u8]) -> Result<(), Error> {
+    let p = find(data).ok_or(Error)?;
+    p.run(data);
+    let q = find(data).ok_or_else(|| Error).map_err(|_| Error)?;
+    q.run(data);
+    Ok(())
+}
+
+fn run(data: &[u8]) {}
+
+fuzz_target!(|data: &[u8]| {
+    Parser::parse(data);
+    run(data);
+    let _ = helper(data);
+});
+";
+        let calls = harness_calls("local_result_helpers", &lib, harness, &CallTreeConfig::default());
+        assert!(has_call(&calls, "Parser::run", 3));
+        assert!(has_call(&calls, "Parser::run", 5));
+        assert!(has_call(&calls, "run", 13));
+        assert!(!has_call(&calls, "Parser::run", 13));
+    }
+
+    #[test]
+    fn local_function_parameters_are_typed() {
+        let harness = "fn exercise(p: &Parser, data: &[u8]) {
+    p.run(data);
+}
+
+fuzz_target!(|data: &[u8]| {
+    exercise(&Parser::new(), data);
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("local_function_parameters", PARSER_LIB, harness, &config);
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 2)));
+    }
+
+    #[test]
+    fn sink_functions_are_flagged() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    b(data);
+});
+";
+        let config = CallTreeConfig { sink_functions: vec!["c".to_string()], ..Default::default() };
+        let lines = call_tree_lines("sink_functions", CALL_CHAIN_LIB, harness, &config);
+        assert_eq!(lines, "  b fuzz/harness.rs linenumber=2\n    c src/lib.rs linenumber=2 SINK\n");
+
+        let config = CallTreeConfig { sink_functions: vec!["from_raw".to_string()], ..Default::default() };
+        assert!(is_sink("Box::from_raw", &config));
+        assert!(!is_sink("Box::my_from_raw", &config));
+    }
+
+    #[test]
+    fn tuple_index_fields_are_typed() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let pair: (u8, Parser) = (data[0], Parser::new());
+    pair.1.run(data);
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("tuple_index_fields", PARSER_LIB, harness, &config);
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 3)));
+    }
+
+    #[test]
+    fn raw_calls_are_kept_when_configured() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    Parser::parse(data); Parser::parse(data);
+});
+";
+        let calls = harness_calls("raw_calls_dropped", PARSER_LIB, harness, &CallTreeConfig::default());
+        assert!(calls.raw_called_functions.is_empty());
+        assert_eq!(calls.called_functions, vec![("Parser::parse".to_string(), 2)]);
+
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("raw_calls_kept", PARSER_LIB, harness, &config);
+        assert_eq!(calls.raw_called_functions, vec![("Parser::parse".to_string(), 2); 2]);
+    }
+
+    #[test]
+    fn single_harness_file_paths_are_discovered() {
+        let dir = write_files("single_harness_file", &[
+            ("fuzz/harness.rs", "fuzz_target!(|data: &[u8]| {});\n"),
+            ("src/lib.rs", PARSER_LIB),
+        ]);
+        let harness_path = dir.join("fuzz/harness.rs").to_string_lossy().to_string();
+        assert_eq!(find_fuzzing_harnesses(&harness_path, &["fuzz_target"]).unwrap(), vec![harness_path.clone()]);
+        assert!(find_fuzzing_harnesses(dir.join("src/lib.rs").to_str().unwrap(), &["fuzz_target"])
+            .unwrap()
+            .is_empty());
+        assert!(has_fuzz_harness(&harness_path, &CallTreeConfig::default()));
+        assert!(!has_fuzz_harness(dir.join("src/lib.rs").to_str().unwrap(), &CallTreeConfig::default()));
+    }
+
+    #[test]
+    fn bounded_visited_set_spills_into_a_bloom_filter() {
+        let names: Vec<String> = (0..32).map(|index| format!("function_{}", index)).collect();
+        let mut exact = VisitedSet::new(None);
+        let mut bounded = VisitedSet::new(Some(4));
+        for name in &names {
+            exact.insert(name);
+            bounded.insert(name);
+        }
+
+        // The bloom filter never forgets a name, it only has false positives
+        assert!(names.iter().all(|name| exact.contains(name) && bounded.contains(name)));
+        assert!(!exact.contains("missing"));
+        assert!(bounded.names.is_empty());
+        assert_eq!(bounded.bloom.len(), 4);
+        assert!(bounded.memory_size() < exact.memory_size());
+    }
+
+    #[test]
+    fn bounded_visited_set_keeps_large_call_trees_in_bounds() {
+        // A binary tree of 2047 functions below each of the three roots called by the harness
+        let mut lib = String::new();
+        for root in ["decode", "encode", "verify"] {
+            lib.push_str(&format!("pub fn {}() {{ {}_1(); {}_2(); }}\n", root, root, root));
+            for index in 1..2047 {
+                let children = if 2 * index + 2 < 2048 {
+                    format!("{}_{}(); {}_{}();", root, 2 * index + 1, root, 2 * index + 2)
+                } else {
+                    String::new()
+                };
+                lib.push_str(&format!("pub fn {}_{}() {{ {} }}\n", root, index, children));
+            }
+        }
+        let harness = "fuzz_target!(|data: &[u8]| {
+    decode();
+    encode();
+    verify();
+});
+";
+        let dir = write_files("bounded_visited_tree", &[("src/lib.rs", &lib), ("fuzz/harness.rs", harness)]);
+        let functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        let fuzz_file = dir.join("fuzz/harness.rs").to_string_lossy().to_string();
+        let function_map: HashMap<String, &FunctionInfo> = functions.iter().map(|f| (f.name.clone(), f)).collect();
+
+        let capacity = 1024;
+        let config = CallTreeConfig { visited_capacity: Some(capacity), ..Default::default() };
+        let calls = extract_called_functions(&fuzz_file, &functions, &config).unwrap();
+        let mut visited = VisitedSet::new(config.visited_capacity);
+        let roots = build_harness_call_trees(&fuzz_file, &calls, &function_map, &mut visited, &config);
+        let root_names: Vec<&str> = roots.iter().map(|root| root.name.as_str()).collect();
+        assert_eq!(root_names, vec!["decode", "encode", "verify"]);
+        assert!(roots.iter().all(|root| !root.revisit && root.children.len() == 2));
+        // Bloom filter false positives only cut a few subtrees short
+        let mut expanded = 0;
+        let mut pending: Vec<&CallTreeNode> = roots.iter().collect();
+        while let Some(node) = pending.pop() {
+            if !node.revisit {
+                expanded += 1;
+                pending.extend(&node.children);
+            }
+        }
+        assert!(expanded > 3 * 2047 * 9 / 10, "{} functions expanded", expanded);
+        assert!(visited.memory_size() <= capacity * std::mem::size_of::<u64>());
+
+        let mut exact = VisitedSet::new(None);
+        build_harness_call_trees(&fuzz_file, &calls, &function_map, &mut exact, &CallTreeConfig::default());
+        assert!(exact.memory_size() > capacity * std::mem::size_of::<u64>() * 4);
+    }
+
+    #[test]
+    fn map_and_vector_accessors_are_typed() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let parsers: HashMap<u8, Parser> = HashMap::new();
+    parsers.get(&data[0]).unwrap().run(data);
+    let list: Vec<Parser> = Vec::new();
+    if let Some(p) = list.first() {
+        p.run(data);
+    }
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("collection_accessors", PARSER_LIB, harness, &config);
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 3)));
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 6)));
+    }
+
+    #[test]
+    fn html_output_links_definitions_and_marks_revisits() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    a(data);
+});
+";
+        let config = CallTreeConfig::default();
+        let html = render_call_trees("html_output", CALL_CHAIN_LIB, harness, &config, |roots| {
+            call_tree_to_html("harness<1>", roots, &config)
+        });
+        assert!(html.contains("<title>harness&lt;1&gt;</title>"));
+        assert!(html.contains(
+            "<li><details open><summary><a href=\"src/lib.rs#L1\">a</a> <span class=\"site\">fuzz/harness.rs:2</span></summary>"
+        ));
+        assert!(html.contains("<li><a href=\"src/lib.rs#L3\">c</a> <span class=\"site\">src/lib.rs:2</span></li>"));
+        assert!(html.contains(
+            "<li><a href=\"src/lib.rs#L3\">c</a> <span class=\"site\">src/lib.rs:1</span> <span class=\"revisit\">(revisit)</span></li>"
+        ));
+    }
+
+    #[test]
+    fn harness_macros_nested_in_wrapper_macros_are_visited() {
+        let harness = "setup! {
+    options => [fast],
+    fuzz_target!(|data: &[u8]| {
+        Parser::parse(data);
+    });
+}
+";
+        let calls = harness_calls("nested_harness_macros", PARSER_LIB, harness, &CallTreeConfig::default());
+        assert!(has_call(&calls, "Parser::parse", 4));
+        assert_eq!(calls.entry_line, Some(3));
+    }
+
+    #[test]
+    fn calls_into_dependency_crates_are_cut_at_the_external_depth() {
+        let dir = write_files("external_depth", &[
+            ("src/lib.rs", "pub fn a(data: &[u8]) { ext1(data); }\n"),
+            ("vendor/dep.rs", "pub fn ext1(data: &[u8]) { ext2(data); }
+pub fn ext2(data: &[u8]) { ext3(data); }
+pub fn ext3(data: &[u8]) {}
+"),
+            ("fuzz/harness.rs", "fuzz_target!(|data: &[u8]| {\n    a(data);\n});\n"),
+        ]);
+        let mut functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        functions.extend(analyse_directory(dir.join("vendor").to_str().unwrap(), &[]).unwrap());
+        let function_map: HashMap<String, &FunctionInfo> = functions.iter().map(|f| (f.name.clone(), f)).collect();
+        let fuzz_file = dir.join("fuzz/harness.rs").to_string_lossy().to_string();
+
+        let tree_names = |config: &CallTreeConfig| {
+            let calls = extract_called_functions(&fuzz_file, &functions, config).unwrap();
+            let mut visited = VisitedSet::new(None);
+            let roots = build_harness_call_trees(&fuzz_file, &calls, &function_map, &mut visited, config);
+            let mut names = Vec::new();
+            let mut node = &roots[0];
+            names.push(node.name.clone());
+            while let Some(child) = node.children.first() {
+                names.push(child.name.clone());
+                node = child;
+            }
+            names
+        };
+
+        let vendor = dir.join("vendor").to_string_lossy().to_string();
+        let config = CallTreeConfig { external_dirs: vec![vendor.clone()], ..Default::default() };
+        assert_eq!(tree_names(&config), vec!["a", "ext1", "ext2", "ext3"]);
+        let config = CallTreeConfig { external_dirs: vec![vendor], external_depth: Some(1), ..Default::default() };
+        assert_eq!(tree_names(&config), vec!["a", "ext1"]);
+    }
+
+    #[test]
+    fn argument_counts_are_emitted_when_configured() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    b(data);
+});
+";
+        let config = CallTreeConfig { emit_arg_counts: true, ..Default::default() };
+        let lines = call_tree_lines("argument_counts", CALL_CHAIN_LIB, harness, &config);
+        assert_eq!(lines, "  b fuzz/harness.rs linenumber=2 args=1\n    c src/lib.rs linenumber=2 args=1\n");
+
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let p = Parser::new();
+    p.run(data);
+});
+";
+        let calls = harness_calls("method_argument_counts", PARSER_LIB, harness, &config);
+        assert_eq!(calls.arg_counts[&("Parser::new".to_string(), 2)], 0);
+        assert_eq!(calls.arg_counts[&("Parser::run".to_string(), 3)], 1);
+    }
+
+    #[test]
+    fn strict_resolution_fails_on_unresolved_project_calls() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    crate::decode(data);
+    Parser::missing(data);
+    std::str::from_utf8(data);
+    Parser::parse(data);
+});
+";
+        let dir = write_files("strict_resolution", &[("src/lib.rs", PARSER_LIB), ("fuzz/harness.rs", harness)]);
+        let functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        let fuzz_dir = dir.join("fuzz").to_string_lossy().to_string();
+        let result = in_output_dir(&dir, || generate_call_trees(&fuzz_dir, &functions, &CallTreeConfig::default()));
+        assert!(result.is_ok());
+
+        let config = CallTreeConfig { strict_resolution: true, ..Default::default() };
+        let err = in_output_dir(&dir, || generate_call_trees(&fuzz_dir, &functions, &config)).unwrap_err();
+        let message = err.to_string().replace(&format!("{}/", dir.display()), "");
+        assert_eq!(
+            message,
+            "unresolved project calls: crate::decode at fuzz/harness.rs:2, Parser::missing at fuzz/harness.rs:3"
+        );
+        assert!(dir.join("fuzzerLogFile-harness.data").exists());
+    }
+
+    #[test]
+    fn methods_resolve_through_smart_pointers_and_cells() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let boxed: Box<Parser> = Box::new(Parser::new());
+    boxed.run(data);
+    let shared: Arc<Mutex<Parser>> = Arc::new(Mutex::new(Parser::new()));
+    shared.lock().unwrap().run(data);
+    let cell: Rc<RefCell<Parser>> = Rc::new(RefCell::new(Parser::new()));
+    cell.borrow_mut().run(data);
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("smart_pointers", PARSER_LIB, harness, &config);
+        for line in [3, 5, 7] {
+            assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), line)));
+        }
+    }
+
+    #[test]
+    fn unreadable_harnesses_are_skipped() {
+        let harness = "fuzz_target!(|data: &[u8]| { Parser::parse(data); });\n";
+        let dir = write_files("unreadable_harnesses", &[
+            ("src/lib.rs", PARSER_LIB),
+            ("fuzz/first.rs", harness),
+            ("fuzz/second.rs", harness),
+        ]);
+        let functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        let expander: crate::config::SourceExpander = Box::new(|path: &str| {
+            if path.ends_with("first.rs") {
+                Err(io::Error::new(io::ErrorKind::NotFound, "removed"))
+            } else {
+                fs::read_to_string(path)
+            }
+        });
+        let config = CallTreeConfig { source_expander: Some(expander), ..Default::default() };
+        let fuzz_dir = dir.join("fuzz").to_string_lossy().to_string();
+        let harnesses = in_output_dir(&dir, || generate_call_trees(&fuzz_dir, &functions, &config)).unwrap();
+        let files: Vec<&String> = harnesses.keys().collect();
+        assert_eq!(files.len(), 1);
+        assert!(files[0].ends_with("second.rs"));
+    }
+
+    #[test]
+    fn match_receivers_are_typed_when_the_arms_agree() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let p = match data.first() {
+        Some(0) => Parser::new(),
+        Some(_) => Parser::parse(data),
+        None => return,
+    };
+    p.run(data);
+    let q = match data.len() {
+        0 => Parser::new(),
+        _ => data.len(),
+    };
+    q.run(data);
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("match_receivers", PARSER_LIB, harness, &config);
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 7)));
+        assert!(calls.raw_called_functions.contains(&("run".to_string(), 12)));
+    }
+
+    #[test]
+    fn ndjson_output_numbers_nodes_and_refers_to_parents() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    a(data);
+});
+";
+        let config = CallTreeConfig::default();
+        let ndjson = render_call_trees("ndjson_output", CALL_CHAIN_LIB, harness, &config, |roots| {
+            call_tree_to_ndjson(roots, &config)
+        });
+        let nodes: Vec<serde_json::Value> = ndjson.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        let summary: Vec<(&str, Option<u64>, u64, bool)> = nodes
+            .iter()
+            .map(|node| {
+                (
+                    node["name"].as_str().unwrap(),
+                    node["parent"].as_u64(),
+                    node["depth"].as_u64().unwrap(),
+                    node["revisit"].as_bool().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(summary, vec![
+            ("a", None, 0, false),
+            ("b", Some(0), 1, false),
+            ("c", Some(1), 2, false),
+            ("c", Some(0), 1, true),
+        ]);
+        assert_eq!(nodes[0]["file"], "fuzz/harness.rs");
+        assert_eq!(nodes[0]["line"], 2);
+        assert_eq!(nodes[3]["id"], 3);
+    }
+
+    #[test]
+    fn unstructured_idiom_is_resolved() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let mut u = Unstructured::new(data);
+    let p = u.arbitrary::<Parser>().unwrap();
+    p.run(data);
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("unstructured_idiom", PARSER_LIB, harness, &config);
+        assert!(calls.raw_called_functions.contains(&("Unstructured::arbitrary".to_string(), 3)));
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 4)));
+    }
+
+    #[test]
+    fn empty_names_never_resolve() {
+        let dir = write_files("empty_names", &[("src/lib.rs", PARSER_LIB)]);
+        let functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        let function_map: HashMap<String, &FunctionInfo> = functions.iter().map(|f| (f.name.clone(), f)).collect();
+        assert!(find_function("", &function_map).is_none());
+        assert!(find_function("  ", &function_map).is_none());
+        assert_eq!(find_function("run", &function_map).map(|f| f.name.as_str()), Some("Parser::run"));
+    }
+
+    #[test]
+    fn visibility_is_emitted_when_configured() {
+        let lib = "pub fn a(data: &[u8]) { b(data); }
+fn b(data: &[u8]) {}
+";
+        let harness = "fuzz_target!(|data: &[u8]| {
+    a(data);
+    external(data);
+});
+";
+        let config = CallTreeConfig { emit_visibility: true, ..Default::default() };
+        let lines = call_tree_lines("visibility_tags", lib, harness, &config);
+        assert_eq!(
+            lines,
+            "  a fuzz/harness.rs linenumber=2 vis=public\n    b src/lib.rs linenumber=1 vis=private\n  external fuzz/harness.rs linenumber=3\n"
+        );
+    }
+
+    #[test]
+    fn reference_conversions_keep_the_receiver_type() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let boxed = Box::new(Parser::new());
+    boxed.as_ref().run(data);
+    let maybe = find(data);
+    maybe.as_ref().unwrap().run(data);
+    let p = Parser::new();
+    p.borrow().run(data);
+});
+";
+        let lib = format!("{}{}", PARSER_LIB, LOAD_LIB);
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("reference_conversions", &lib, harness, &config);
+        for line in [3, 5, 7] {
+            assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), line)));
+        }
+    }
+
+    // Types `<expr>.inner()` on a project specific `Handle` wrapper as the wrapped `Parser`
+    struct HandleResolver;
+
+    impl crate::config::ReceiverResolver for HandleResolver {
+        fn resolve_type(&self, expr: &Expr, type_of: &dyn Fn(&Expr) -> Option<String>) -> Option<String> {
+            match expr {
+                Expr::MethodCall(method_call) if method_call.method == "inner" => {
+                    (type_name(&type_of(&method_call.receiver)?) == "Handle").then(|| "Parser".to_string())
+                }
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn receiver_resolvers_type_what_the_builtin_rules_cannot() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let handle: Handle = open(data);
+    handle.inner().run(data);
+    let p = handle.inner();
+    p.run(data);
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("no_receiver_resolvers", PARSER_LIB, harness, &config);
+        assert!(!calls.raw_called_functions.contains(&("Parser::run".to_string(), 3)));
+
+        let config = CallTreeConfig {
+            keep_raw_calls: true,
+            receiver_resolvers: vec![Box::new(HandleResolver)],
+            ..Default::default()
+        };
+        let calls = harness_calls("receiver_resolvers", PARSER_LIB, harness, &config);
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 3)));
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 5)));
+    }
+
+    #[test]
+    fn opaque_trait_values_qualify_methods_with_the_trait() {
+        let lib = "pub trait Decode { fn decode(&self, data: &[u8]); }
+pub struct Raw;
+impl Decode for Raw { fn decode(&self, data: &[u8]) {} }
+pub fn decoder() -> impl Decode { Raw }
+pub fn boxed() -> Box<dyn Decode> { Box::new(Raw) }
+";
+        let harness = "fuzz_target!(|data: &[u8]| {
+    decoder().decode(data);
+    boxed().decode(data);
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("opaque_trait_values", lib, harness, &config);
+        assert!(calls.raw_called_functions.contains(&("Decode::decode".to_string(), 2)));
+        assert!(calls.raw_called_functions.contains(&("Decode::decode".to_string(), 3)));
+    }
+
+    #[test]
+    fn root_overrides_replace_the_detected_calls() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    Parser::new();
+    Parser::parse(data);
+});
+";
+        let config = CallTreeConfig {
+            root_overrides: HashMap::from([(
+                "harness".to_string(),
+                vec!["Parser::run".to_string(), "Parser::new".to_string()],
+            )]),
+            ..Default::default()
+        };
+        let calls = harness_calls("root_overrides", PARSER_LIB, harness, &config);
+        assert_eq!(calls.called_functions, vec![
+            ("Parser::run".to_string(), 1),
+            ("Parser::new".to_string(), 2),
+        ]);
+    }
+
+    #[test]
+    fn try_block_statements_are_visited_and_scoped() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let result: Result<(), Error> = try {
+        let p = load(data)?;
+        p.run(data);
+    };
+});
+";
+        let lib = format!("{}{}", PARSER_LIB, LOAD_LIB);
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("try_blocks", &lib, harness, &config);
+        assert!(calls.raw_called_functions.contains(&("load".to_string(), 3)));
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 4)));
+    }
+
+    #[test]
+    fn call_depths_are_emitted_when_configured() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    b(data);
+});
+";
+        let config = CallTreeConfig { emit_depths: true, ..Default::default() };
+        let lines = call_tree_lines("emit_depths", CALL_CHAIN_LIB, harness, &config);
+        assert_eq!(lines, "  b fuzz/harness.rs linenumber=2 depth=1\n    c src/lib.rs linenumber=2 depth=2\n");
+    }
+
+    #[test]
+    fn moved_out_values_are_typed() {
+        let harness = "struct State {
+    parser: Parser,
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut state = State { parser: Parser::new() };
+    std::mem::take(&mut state.parser).run(data);
+    let old = mem::replace(&mut state.parser, Parser::parse(data));
+    old.run(data);
+    take(&mut state.parser).run(data);
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("mem_take_replace", PARSER_LIB, harness, &config);
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 7)));
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 9)));
+        assert!(!calls.raw_called_functions.contains(&("Parser::run".to_string(), 10)));
+    }
+
+    #[test]
+    fn streaming_discovery_matches_the_collected_harnesses() {
+        let harness = "fuzz_target!(|data: &[u8]| {});\n";
+        let dir = write_files("streaming_discovery", &[
+            ("fuzz/a.rs", harness),
+            ("fuzz/nested/b.rs", harness),
+            ("fuzz/nested/deeper/c.rs", harness),
+            ("fuzz/helper.rs", "pub fn helper() {}\n"),
+            ("fuzz/notes.txt", "fuzz_target!"),
+        ]);
+        let fuzz_dir = dir.join("fuzz").to_string_lossy().to_string();
+        let config = CallTreeConfig::default();
+        let streamed: Vec<String> = discover_harnesses_iter(&fuzz_dir, &config).map(Result::unwrap).collect();
+        assert_eq!(streamed, find_fuzzing_harnesses(&fuzz_dir, &["fuzz_target"]).unwrap());
+        assert_eq!(streamed.len(), 3);
+
+        let mut missing = discover_harnesses_iter(dir.join("missing").to_str().unwrap(), &config);
+        assert!(matches!(missing.next(), Some(Err(_))));
+        assert!(missing.next().is_none());
+    }
+
+    #[test]
+    fn statics_and_consts_type_their_method_calls() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    PARSER.run(data);
+    DEFAULT.run(data);
+});
+
+static PARSER: LazyLock<Parser> = LazyLock::new(Parser::new);
+const DEFAULT: Parser = Parser;
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("statics_and_consts", PARSER_LIB, harness, &config);
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 2)));
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 3)));
+    }
+
+    #[test]
+    fn immediately_invoked_closures_are_visited() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let _ = (|| -> Result<(), Error> {
+        load(data)?.run(data);
+        Ok(())
+    })();
+});
+";
+        let lib = format!("{}{}", PARSER_LIB, LOAD_LIB);
+        let calls = harness_calls("immediately_invoked_closures", &lib, harness, &CallTreeConfig::default());
+        assert!(has_call(&calls, "load", 3));
+        assert!(has_call(&calls, "Parser::run", 3));
+    }
+
+    #[test]
+    fn written_names_are_emitted_when_configured() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let p = crate::Parser::parse(data);
+    p.run(data);
+});
+";
+        let config = CallTreeConfig { emit_written_names: true, ..Default::default() };
+        let lines = call_tree_lines("written_names", PARSER_LIB, harness, &config);
+        assert_eq!(
+            lines,
+            "  Parser::parse fuzz/harness.rs linenumber=2 written=crate::Parser::parse\n  Parser::run fuzz/harness.rs linenumber=3 written=run\n"
+        );
+    }
+
+    #[test]
+    fn harnesses_of_overlapping_dirs_are_generated_once() {
+        let harness = "fuzz_target!(|data: &[u8]| { Parser::parse(data); });\n";
+        let dir = write_files("generate_for_dirs", &[
+            ("src/lib.rs", PARSER_LIB),
+            ("fuzz/fuzz_targets/first.rs", harness),
+            ("examples/second.rs", harness),
+        ]);
+        let functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        let fuzz_dir = dir.join("fuzz").to_string_lossy().to_string();
+        let targets_dir = dir.join("fuzz/fuzz_targets").to_string_lossy().to_string();
+        let examples_dir = dir.join("examples").to_string_lossy().to_string();
+        let harnesses = in_output_dir(&dir, || {
+            generate_call_trees_for_dirs(&[&fuzz_dir, &targets_dir, &examples_dir], &functions, &CallTreeConfig::default())
+        })
+        .unwrap();
+
+        let mut files: Vec<String> = harnesses.keys().map(|file| file.replace(&format!("{}/", dir.display()), "")).collect();
+        files.sort();
+        assert_eq!(files, vec!["examples/second.rs", "fuzz/fuzz_targets/first.rs"]);
+        assert!(dir.join("fuzzerLogFile-first.data").exists());
+        assert!(dir.join("fuzzerLogFile-second.data").exists());
+    }
+
+    #[test]
+    fn ambiguous_methods_prefer_the_trait_in_scope() {
+        let lib = "pub trait Decode { fn process(&self); }
+pub trait Encode { fn process(&self); }
+pub struct Raw;
+impl Decode for Raw { fn process(&self) {} }
+impl Encode for Raw { fn process(&self) {} }
+";
+        let harness = "use mylib::Encode;
+
+fuzz_target!(|data: &[u8]| {
+    let raw: Raw = make(data);
+    raw.process();
+});
+";
+        let dir = write_files("trait_in_scope", &[("src/lib.rs", lib), ("fuzz/harness.rs", harness)]);
+        let functions =
+            crate::analyse::canonicalize_functions(analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap());
+        let encode = functions.iter().find(|f| f.trait_name.as_deref() == Some("Encode")).unwrap();
+        assert_ne!(encode.name, "Raw::process");
+
+        let fuzz_file = dir.join("fuzz/harness.rs").to_string_lossy().to_string();
+        let calls = extract_called_functions(&fuzz_file, &functions, &CallTreeConfig::default()).unwrap();
+        assert!(has_call(&calls, &encode.name, 5));
+    }
+
+    #[test]
+    fn reachable_complexity_counts_each_function_once() {
+        let lib = "pub fn a(data: &[u8]) { b(data); c(data); }
+pub fn b(data: &[u8]) { if data.is_empty() { c(data); } }
+pub fn c(data: &[u8]) {}
+pub fn unreached(data: &[u8]) { if data.is_empty() {} }
+";
+        let harness = "fuzz_target!(|data: &[u8]| {
+    a(data);
+    b(data);
+});
+";
+        let dir = write_files("reachable_complexity", &[("src/lib.rs", lib), ("fuzz/harness.rs", harness)]);
+        let functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        let complexity = |name: &str| functions.iter().find(|f| f.name == name).unwrap().complexity;
+        let harnesses = build_harness_function_infos(dir.to_str().unwrap(), &functions, &CallTreeConfig::default())
+            .unwrap();
+        let harness = harnesses.values().next().unwrap();
+        assert_eq!(harness.reachable_complexity, complexity("a") + complexity("b") + complexity("c"));
+        assert!(complexity("b") > complexity("c"));
+    }
+
+    #[test]
+    fn guards_returned_by_getters_are_dereferenced() {
+        let lib = format!(
+            "{}pub struct Shared;
+impl Shared {{
+    pub fn parser(&self) -> MutexGuard<'_, Parser> {{ todo!() }}
+    pub fn cached(&self) -> Ref<'_, Parser> {{ todo!() }}
+}}
+",
+            PARSER_LIB
+        );
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let shared: Shared = make(data);
+    shared.parser().run(data);
+    let cached = shared.cached();
+    cached.run(data);
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("guard_getters", &lib, harness, &config);
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 3)));
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 5)));
+    }
+
+    #[test]
+    fn harness_limit_keeps_the_first_harnesses_by_path() {
+        let harness = "fuzz_target!(|data: &[u8]| { Parser::parse(data); });\n";
+        let dir = write_files("harness_limit", &[
+            ("src/lib.rs", PARSER_LIB),
+            ("fuzz/c.rs", harness),
+            ("fuzz/a.rs", harness),
+            ("fuzz/b.rs", harness),
+        ]);
+        let functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        let fuzz_dir = dir.join("fuzz").to_string_lossy().to_string();
+        for stream_discovery in [false, true] {
+            let config = CallTreeConfig { max_harnesses: Some(2), stream_discovery, ..Default::default() };
+            let harnesses = in_output_dir(&dir, || generate_call_trees(&fuzz_dir, &functions, &config)).unwrap();
+            let mut files: Vec<String> = harnesses.keys().map(|file| file.replace(&format!("{}/", dir.display()), "")).collect();
+            files.sort();
+            assert_eq!(files, vec!["fuzz/a.rs", "fuzz/b.rs"]);
+        }
+
+        let config = CallTreeConfig { max_harnesses: Some(1), ..Default::default() };
+        let harnesses = build_harness_function_infos(&fuzz_dir, &functions, &config).unwrap();
+        assert!(harnesses.keys().all(|file| file.ends_with("a.rs")));
+        assert_eq!(harnesses.len(), 1);
+    }
+
+    #[test]
+    fn loop_variables_are_typed_from_the_item_type() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let parsers: Vec<Parser> = Vec::new();
+    for p in parsers.into_iter().rev() {
+        p.run(data);
+    }
+    let named: HashMap<String, Parser> = HashMap::new();
+    for (i, (name, p)) in named.into_iter().enumerate() {
+        p.run(data);
+    }
+    let mut items = parsers.iter();
+    items.next().unwrap().run(data);
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("loop_variables", PARSER_LIB, harness, &config);
+        for line in [4, 8, 11] {
+            assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), line)), "line {}", line);
+        }
+    }
+
+    #[test]
+    fn borrowed_loops_bind_references_to_the_elements() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let parsers: Vec<Parser> = Vec::new();
+    for p in &parsers {
+        p.run(data);
+    }
+    for &p in parsers.iter() {
+        p.run(data);
+    }
+    let named: HashMap<String, Parser> = HashMap::new();
+    for p in named.values() {
+        p.run(data);
+    }
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("borrowed_loops", PARSER_LIB, harness, &config);
+        for line in [4, 7, 11] {
+            assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), line)), "line {}", line);
+        }
+    }
+
+    #[test]
+    fn fuzz_input_rebound_through_from_is_typed() {
+        let lib = "pub struct Input;
+impl Input {
+    pub fn decode(&self) {}
+}
+";
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let input = Input::from(data);
+    input.decode();
+    let checked = Input::try_from(data).unwrap();
+    checked.decode();
+    let parsed = Input::from_str(\"text\")?;
+    parsed.decode();
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("from_conversions", lib, harness, &config);
+        for line in [3, 5, 7] {
+            assert!(calls.raw_called_functions.contains(&("Input::decode".to_string(), line)), "line {}", line);
+        }
+    }
+
+    #[test]
+    fn worker_threads_generate_the_same_harnesses() {
+        let dir = write_files("worker_threads", &[
+            ("src/lib.rs", PARSER_LIB),
+            ("fuzz/a.rs", "fuzz_target!(|data: &[u8]| { Parser::parse(data); });\n"),
+            ("fuzz/b.rs", "fuzz_target!(|data: &[u8]| { Parser::new().run(data); });\n"),
+            ("fuzz/c.rs", "fuzz_target!(|data: &[u8]| { Parser::new(); });\n"),
+        ]);
+        let functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        let fuzz_dir = dir.join("fuzz").to_string_lossy().to_string();
+        let reached = |config: &CallTreeConfig| {
+            let harnesses = in_output_dir(&dir, || generate_call_trees(&fuzz_dir, &functions, config)).unwrap();
+            let mut reached: Vec<(String, Vec<String>)> = harnesses
+                .into_iter()
+                .map(|(file, harness)| (file, harness.called_functions))
+                .collect();
+            reached.sort();
+            reached
+        };
+
+        let data_files = || -> Vec<String> {
+            ["a", "b", "c"]
+                .iter()
+                .map(|name| fs::read_to_string(dir.join(format!("fuzzerLogFile-{}.data", name))).unwrap())
+                .collect()
+        };
+
+        let sequential = reached(&CallTreeConfig::default());
+        let sequential_data = data_files();
+        assert_eq!(sequential.len(), 3);
+        assert!(sequential_data[1].contains("Parser::run"));
+        for worker_threads in [None, Some(1), Some(2), Some(8)] {
+            for name in ["a", "b", "c"] {
+                let _ = fs::remove_file(dir.join(format!("fuzzerLogFile-{}.data", name)));
+            }
+            let concurrent = reached(&CallTreeConfig { worker_threads, ..Default::default() });
+            assert_eq!(concurrent, sequential, "worker_threads {:?}", worker_threads);
+            assert_eq!(data_files(), sequential_data, "worker_threads {:?}", worker_threads);
+        }
+    }
+
+    #[test]
+    fn filter_and_or_adapters_keep_the_wrapped_type() {
+        let lib = format!("{}{}", PARSER_LIB, LOAD_LIB);
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let p = find(data).filter(|p| true).or(None).unwrap();
+    p.run(data);
+    load(data).or_else(|_| load(data)).unwrap().run(data);
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("try_preserving_adapters", &lib, harness, &config);
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 3)));
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 4)));
+    }
+
+    #[test]
+    fn blocklisted_roots_are_not_expanded() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    a(data);
+    c(data);
+});
+";
+        let config = CallTreeConfig { root_blocklist: vec!["a".to_string()], ..Default::default() };
+        let lines = call_tree_lines("root_blocklist", CALL_CHAIN_LIB, harness, &config);
+        assert_eq!(lines, "  a fuzz/harness.rs linenumber=2\n  c fuzz/harness.rs linenumber=3\n");
+    }
+
+    #[test]
+    fn alias_qualified_constructors_keep_the_alias_target() {
+        let harness = "type Parsers = Vec<Parser>;
+type Checked = Vec<Parser>;
+
+fuzz_target!(|data: &[u8]| {
+    let parsers = Parsers::new();
+    parsers[0].run(data);
+    let checked = Checked::try_from(data).unwrap();
+    checked[0].run(data);
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("alias_constructors", PARSER_LIB, harness, &config);
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 6)));
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 8)));
+    }
+
+    #[test]
+    fn harness_panics_are_caught_when_configured() {
+        let dir = write_files("catch_panics", &[
+            ("src/lib.rs", PARSER_LIB),
+            ("fuzz/a.rs", "fuzz_target!(|data: &[u8]| { Parser::parse(data); });\n"),
+            ("fuzz/b.rs", "fuzz_target!(|data: &[u8]| { Parser::new(); });\n"),
+            ("fuzz/c.rs", "fuzz_target!(|data: &[u8]| { Parser::new().run(data); });\n"),
+        ]);
+        let functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        let fuzz_dir = dir.join("fuzz").to_string_lossy().to_string();
+        let expander: crate::config::SourceExpander = Box::new(|path: &str| {
+            if path.ends_with("b.rs") {
+                panic!("unexpected shape");
+            }
+            fs::read_to_string(path)
+        });
+        let config = CallTreeConfig { catch_panics: true, source_expander: Some(expander), ..Default::default() };
+        let harnesses = in_output_dir(&dir, || generate_call_trees(&fuzz_dir, &functions, &config)).unwrap();
+        let mut harness_files: Vec<&str> = harnesses.keys().map(String::as_str).collect();
+        harness_files.sort();
+        assert_eq!(harness_files, vec![
+            dir.join("fuzz/a.rs").to_str().unwrap(),
+            dir.join("fuzz/c.rs").to_str().unwrap(),
+        ]);
+        assert!(fs::read_to_string(dir.join("fuzzerLogFile-a.data")).unwrap().contains("Parser::parse"));
+        assert!(fs::read_to_string(dir.join("fuzzerLogFile-c.data")).unwrap().contains("Parser::run"));
+        assert!(!dir.join("fuzzerLogFile-b.data").exists());
+
+        let uncaught = panic::catch_unwind(|| {
+            let _ = catch_harness_panic::<()>("fuzz/harness.rs", &CallTreeConfig::default(), || panic!("unexpected shape"));
+        });
+        assert!(uncaught.is_err());
+    }
+
+    #[test]
+    fn single_item_iterator_methods_are_typed() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    let parsers: Vec<Parser> = Vec::new();
+    parsers.into_iter().last().unwrap().run(data);
+    let found = parsers.iter().find(|p| true).unwrap();
+    found.run(data);
+    parsers.last().unwrap().run(data);
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("single_item_iterator_methods", PARSER_LIB, harness, &config);
+        for line in [3, 5, 6] {
+            assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), line)), "line {}", line);
+        }
+    }
+
+    #[test]
+    fn entry_signature_describes_the_fuzz_input() {
+        let config = CallTreeConfig::default();
+        let harness = "fuzz_target!(|data: &[u8]| { Parser::parse(data); });\n";
+        let calls = harness_calls("entry_signature", PARSER_LIB, harness, &config);
+        assert_eq!(
+            calls.entry_signature,
+            Some(EntrySignature { arg_names: vec!["data".to_string()], arg_types: vec!["& [u8]".to_string()] })
+        );
+
+        let harness = "fuzz_target!(init: Parser::new(), |(a, b): (u8, u8), input| {});\n";
+        let calls = harness_calls("labelled_entry_signature", PARSER_LIB, harness, &config);
+        assert_eq!(
+            calls.entry_signature,
+            Some(EntrySignature {
+                arg_names: vec!["_".to_string(), "input".to_string()],
+                arg_types: vec!["(u8 , u8)".to_string(), "_".to_string()],
+            })
+        );
+
+        let harness = "fuzz_target!(|data: &[u8]| { Parser::parse(data); });\n";
+        let dir = write_files("harness_arguments", &[("src/lib.rs", PARSER_LIB), ("fuzz/harness.rs", harness)]);
+        let functions = analyse_directory(dir.join("src").to_str().unwrap(), &[]).unwrap();
+        let harnesses = build_harness_function_infos(dir.to_str().unwrap(), &functions, &config).unwrap();
+        let harness = harnesses.values().next().unwrap();
+        assert_eq!(harness.arg_count, 1);
+        assert_eq!(harness.arg_names, vec!["data"]);
+        assert_eq!(harness.arg_types, vec!["& [u8]"]);
+    }
+
+    #[test]
+    fn unit_struct_values_are_typed_receivers() {
+        let harness = "struct Local;
+
+fuzz_target!(|data: &[u8]| {
+    Parser.run(data);
+    Local.check(data);
+    data.len();
+});
+";
+        let config = CallTreeConfig { keep_raw_calls: true, ..Default::default() };
+        let calls = harness_calls("unit_struct_receivers", PARSER_LIB, harness, &config);
+        assert!(calls.raw_called_functions.contains(&("Parser::run".to_string(), 4)));
+        assert!(calls.raw_called_functions.contains(&("Local::check".to_string(), 5)));
+        assert!(!calls.raw_called_functions.iter().any(|(name, _)| name.starts_with("data::")));
+    }
+
+    #[test]
+    fn ndjson_snippets_are_the_call_site_lines() {
+        let harness = "fuzz_target!(|data: &[u8]| {
+    b(data);
+});
+";
+        let config = CallTreeConfig { emit_snippets: true, ..Default::default() };
+        let ndjson = render_call_trees("ndjson_snippets", CALL_CHAIN_LIB, harness, &config, |roots| {
+            call_tree_to_ndjson(roots, &config)
+        });
+        let snippets: Vec<serde_json::Value> = ndjson
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["snippet"].clone())
+            .collect();
+        assert_eq!(snippets, vec!["b(data);", "pub fn b(data: &[u8]) { c(data); }"]);
+
+        let mut source_lines = SourceLineCache::default();
+        assert_eq!(source_lines.line("missing.rs", 1), None);
+        let config = CallTreeConfig::default();
+        let ndjson = render_call_trees("ndjson_without_snippets", CALL_CHAIN_LIB, harness, &config, |roots| {
+            call_tree_to_ndjson(roots, &config)
+        });
+        assert!(!ndjson.contains("snippet"));
+    }
+
+    #[test]
+    fn fuzz_harness_precheck_honours_entry_macros() {
+        let dir = write_files("precheck_entry_macros", &[
+            ("src/lib.rs", PARSER_LIB),
+            ("fuzz/harness.rs", "my_fuzz!(|data: &[u8]| { Parser::parse(data); });\n"),
+        ]);
+        let config = CallTreeConfig { entry_macros: vec!["my_fuzz".to_string()], ..Default::default() };
+        let dir = dir.to_str().unwrap();
+        assert!(has_fuzz_harness(dir, &config));
+        assert_eq!(find_fuzzing_harnesses(dir, &entry_macro_names(&config)).unwrap().len(), 1);
+        assert!(!has_fuzz_harness(dir, &CallTreeConfig::default()));
+    }
 }
@@ -0,0 +1,257 @@
+/* Copyright 2024 Fuzz Introspector Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+// Callback returning the macro expanded source of a harness file, e.g. from `cargo expand`.
+// Callbacks and resolvers are shared with the worker threads of the call tree generation.
+pub type SourceExpander = Box<dyn Fn(&str) -> io::Result<String> + Send + Sync>;
+
+// Callback rewriting a resolved function name for the call tree output
+pub type NameRewriter = Box<dyn Fn(&str) -> String + Send + Sync>;
+
+// Extension point typing a harness expression that the built-in receiver typing cannot, e.g.
+// the accessor of a project specific wrapper. It is consulted for every expression, including
+// the subexpressions of a method chain, once the built-in rules give up. `type_of` types
+// another expression with the built-in rules and all registered resolvers. The type is
+// returned as rust type source, e.g. `Parser` or `Vec<Item>`.
+pub trait ReceiverResolver: Send + Sync {
+    fn resolve_type(&self, expr: &syn::Expr, type_of: &dyn Fn(&syn::Expr) -> Option<String>) -> Option<String>;
+}
+
+// Base struct for user configurable options of the call tree generation
+#[derive(Default)]
+pub struct CallTreeConfig {
+    // Registry of project specific harness macros mapped to the expression they expand to.
+    // The expansion template is plain rust expression source where `$1`, `$2`, ... are
+    // substituted by the comma separated macro arguments, e.g. registering `my_fuzz` with
+    // `$1::$2(data)` makes `my_fuzz!(Parser, parse)` record the call `Parser::parse`.
+    pub macro_patterns: HashMap<String, String>,
+
+    // Crate name prefixes (e.g. `mylib::`) of calls to keep in the call tree even though they
+    // are not project functions. All unresolved calls are kept when this is None, and only
+    // project functions are kept when the list is empty.
+    pub crate_allowlist: Option<Vec<String>>,
+
+    // Source path prefixes of dependency crates, e.g. a vendor directory, whose functions are
+    // analysed and passed in together with the project functions. Functions defined under
+    // them are external, and chains of calls into them are followed for at most
+    // external_depth levels. They are followed without limit when that is None.
+    pub external_dirs: Vec<String>,
+    pub external_depth: Option<usize>,
+
+    // Also output the call tree of each harness as fuzzerLogFile-<harness>.dot
+    pub emit_dot: bool,
+
+    // Also output the call tree of each harness as a self-contained HTML page,
+    // fuzzerLogFile-<harness>.html, with collapsible calls linking to their definition
+    pub emit_html: bool,
+
+    // Also output the call tree of each harness as newline delimited JSON,
+    // fuzzerLogFile-<harness>.ndjson, with one node object per line in depth first order
+    pub emit_ndjson: bool,
+
+    // Attach the trimmed source line of each call site to its node in the ndjson output as
+    // `snippet`, which is null when the line is unknown or out of range
+    pub emit_snippets: bool,
+
+    // Also output the functions reached by all harnesses as compact bitsets over the sorted
+    // function names to fuzzerReachability.json, see reachability::ReachabilityBitsets
+    pub emit_reachability_bitsets: bool,
+
+    // Append the stable id of the resolved function, as given by FunctionInfo::id, to each
+    // .data call tree line. Extended fields are not understood by the LLVM format parser.
+    pub emit_node_ids: bool,
+
+    // Append the callee of each harness call as written in the source, as `written=<text>`,
+    // to its .data call tree line, e.g. `written=run` for a call resolved to `Parser::run`
+    pub emit_written_names: bool,
+
+    // Append the number of arguments passed at each call, as `args=<count>`, to each .data
+    // call tree line whose call site argument count is known
+    pub emit_arg_counts: bool,
+
+    // Append the visibility of the resolved function, as recorded in FunctionInfo, to each
+    // .data call tree line, e.g. `vis=private` where the harness reaches private internals
+    pub emit_visibility: bool,
+
+    // Append the call depth of each node, as `depth=<depth>`, to each .data call tree line so
+    // consumers need not derive it from the indentation. Roots of the harness are at depth 1.
+    pub emit_depths: bool,
+
+    // Use the outermost calls of every top-level statement of the harness body as the roots,
+    // in statement order, instead of every call found in the harness sorted by line. The other
+    // calls of a statement are nested under the call enclosing them.
+    pub statement_roots: bool,
+
+    // Scan the tokens of macro bodies that cannot be parsed as expressions for `name(` call
+    // patterns. The found calls are best effort and also listed as heuristic calls.
+    pub scan_macro_tokens: bool,
+
+    // Compare the argument count of each harness call with the resolved function and keep
+    // the call unresolved with a warning when they differ
+    pub check_call_arity: bool,
+
+    // Analyse the source returned by this callback for each harness file instead of the raw
+    // file, so calls hidden behind macros are found in their expanded form
+    pub source_expander: Option<SourceExpander>,
+
+    // Leave out the calls inside closures and async blocks passed to spawn functions such as
+    // std::thread::spawn or tokio::spawn, which are treated as reachable by default
+    pub skip_spawned_closures: bool,
+
+    // Indentation unit repeated per call depth in the .data output, e.g. "\t". Two spaces are
+    // used when this is None, which is the only indentation the LLVM format parser accepts.
+    pub indent: Option<String>,
+
+    // Fail the call tree generation, after all output is written, when a call looks like a
+    // project call but is not resolved, e.g. `crate::decode` or a missing method of a project
+    // type. The error lists every such call, so resolution gaps fail CI.
+    pub strict_resolution: bool,
+
+    // Only process the first N harnesses sorted by path, e.g. for a quick smoke test of an
+    // enormous project. All harnesses are discovered first to select them deterministically,
+    // so this disables stream_discovery.
+    pub max_harnesses: Option<usize>,
+
+    // Generate the call tree of each harness as soon as it is discovered instead of after the
+    // whole source directory has been scanned. A discovery error then ends the generation
+    // after the output of the harnesses found before it has been written.
+    pub stream_discovery: bool,
+
+    // Generate the call trees and output files of up to N harnesses at once on a bounded pool
+    // of worker threads, overlapping the parsing of some harnesses with the output of others.
+    // Harnesses are processed one after another on the calling thread when this is None. All
+    // harnesses are discovered first to be shared out, so this disables stream_discovery.
+    pub worker_threads: Option<usize>,
+
+    // Isolate the harnesses from each other by catching a panic raised while analysing one,
+    // e.g. by an unexpected syntax shape reaching the visitor. The panicking harness is skipped
+    // with a warning, possibly leaving partial output files, and the others are still done.
+    pub catch_panics: bool,
+
+    // Log the time spent parsing, walking and building the call tree of each harness, with the
+    // bytes held by its visited set and by its .data call tree lines
+    pub log_timings: bool,
+
+    // Nest the calls made in the arguments of a harness call under that call in the call
+    // tree, e.g. `g` under `f` for `f(g())`, instead of listing them as separate roots
+    pub nest_argument_calls: bool,
+
+    // Rewrite every node name right before it is written to the .data and DOT output, e.g. to
+    // strip a crate prefix expected to be absent by a report backend
+    pub name_rewriter: Option<NameRewriter>,
+
+    // Custom receiver type resolvers consulted in order when the built-in typing of an
+    // expression fails
+    pub receiver_resolvers: Vec<Box<dyn ReceiverResolver>>,
+
+    // Explicit root functions of the call trees of a harness, by harness name as used for the
+    // output files, e.g. `fuzz-parser`. They replace the calls detected in its entry macro.
+    pub root_overrides: HashMap<String, Vec<String>>,
+
+    // Names of custom harness entry macros recognised like fuzz_target, both when discovering
+    // harness files and when visiting them
+    pub entry_macros: Vec<String>,
+
+    // Only output the first N call levels of each harness call tree in the .data output for
+    // a compact overview. Each cut node gets the number of deeper calls left out appended as
+    // an extended `omitted=<count>` field.
+    pub overview_depth: Option<usize>,
+
+    // Names of dangerous sink functions, e.g. `from_raw` or `mylib::ffi::decode`, flagged with
+    // an extended `SINK` field on each matching .data call tree line. A name matches a node
+    // when it equals the node name or its trailing path segments.
+    pub sink_functions: Vec<String>,
+
+    // Names of setup functions called by harness bodies, e.g. `init_logger` or
+    // `env_logger::init`, whose root calls are recorded as leaves without expanding the
+    // functions they call. Names match like sink_functions.
+    pub root_blocklist: Vec<String>,
+
+    // Keep the calls collected by the harness visitor in visiting order, before duplicates are
+    // removed and names are post processed, to debug which of the stages loses a call
+    pub keep_raw_calls: bool,
+
+    // Maximum number of expanded function names remembered exactly while building the call
+    // trees of a harness. Past it the names move into a fixed size bloom filter which keeps
+    // memory bounded, but may cut a few subtrees as if their function was already expanded.
+    pub visited_capacity: Option<usize>,
+}
+
+// Harness entry macro declared in a config file, optionally with the expansion template used
+// for macro_patterns when the macro body is not a closure over the input
+#[derive(Deserialize)]
+struct EntryMacro {
+    name: String,
+    #[serde(default)]
+    expansion: Option<String>,
+}
+
+// Base struct for the YAML config file of the call tree generation
+#[derive(Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    entry_macros: Vec<EntryMacro>,
+}
+
+impl CallTreeConfig {
+    // Add the harness entry macros listed in a YAML config file, e.g.
+    //
+    // entry_macros:
+    //   - name: my_fuzz
+    //   - name: fuzz_parser
+    //     expansion: "$1::parse(data)"
+    pub fn load_entry_macros(&mut self, path: &str) -> io::Result<()> {
+        let content = fs::read_to_string(path)?;
+        let config_file: ConfigFile = serde_yaml::from_str(&content).map_err(io::Error::other)?;
+        for entry_macro in config_file.entry_macros {
+            if let Some(expansion) = entry_macro.expansion {
+                self.macro_patterns.insert(entry_macro.name.clone(), expansion);
+            }
+            if !self.entry_macros.contains(&entry_macro.name) {
+                self.entry_macros.push(entry_macro.name);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_macros_are_loaded_from_a_config_file() {
+        let path = std::env::temp_dir().join(format!("rust_function_analyser-{}-entry_macros.yaml", std::process::id()));
+        fs::write(
+            &path,
+            "entry_macros:\n  - name: my_fuzz\n  - name: fuzz_parser\n    expansion: \"$1::parse(data)\"\n",
+        )
+        .unwrap();
+
+        let mut config = CallTreeConfig { entry_macros: vec!["my_fuzz".to_string()], ..Default::default() };
+        config.load_entry_macros(path.to_str().unwrap()).unwrap();
+        assert_eq!(config.entry_macros, vec!["my_fuzz", "fuzz_parser"]);
+        assert_eq!(config.macro_patterns.get("fuzz_parser").map(String::as_str), Some("$1::parse(data)"));
+        assert!(!config.macro_patterns.contains_key("my_fuzz"));
+
+        assert!(config.load_entry_macros("missing.yaml").is_err());
+    }
+}
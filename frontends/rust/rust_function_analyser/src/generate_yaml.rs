@@ -16,11 +16,10 @@
 use crate::analyse::FunctionInfo;
 
 use serde::{Serialize, Deserialize};
-use serde_yaml;
 
 use std::fs::File;
 use std::io::{self, Write};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 
 // Base struct for data.yaml files
@@ -41,6 +40,47 @@ struct FunctionSection {
     elements: Vec<FunctionInfo>,
 }
 
+// Output file of the function index
+const FUNCTION_INDEX_FILE: &str = "functions-index.json";
+
+// Entry of the function index, giving the definition location of a function as
+// `file:start_line:end_line` together with its key metrics
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FunctionIndexEntry {
+    pub location: String,
+    #[serde(rename = "CyclomaticComplexity")]
+    pub complexity: usize,
+    #[serde(rename = "functionDepth")]
+    pub depth: usize,
+    #[serde(rename = "functionUses")]
+    pub function_uses: usize,
+}
+
+// Build the index from qualified function name to definition location, ordered by name. A
+// name defined more than once keeps its first definition, unless the functions were
+// canonicalised to distinct names beforehand.
+pub fn build_function_index(functions: &[FunctionInfo]) -> BTreeMap<String, FunctionIndexEntry> {
+    let mut index = BTreeMap::new();
+    for function in functions {
+        index.entry(function.name.clone()).or_insert_with(|| FunctionIndexEntry {
+            location: format!("{}:{}:{}", function.file, function.start_line, function.end_line),
+            complexity: function.complexity,
+            depth: function.depth,
+            function_uses: function.function_uses,
+        });
+    }
+    index
+}
+
+// Write the function index once for all harnesses, so that report viewers and editors can
+// jump to a function without reading the call tree or YAML outputs
+pub fn generate_function_index(functions: &[FunctionInfo]) -> io::Result<()> {
+    let index = build_function_index(functions);
+    let json_data = serde_json::to_string_pretty(&index).map_err(io::Error::other)?;
+    let mut file = File::create(FUNCTION_INDEX_FILE)?;
+    file.write_all(json_data.as_bytes())
+}
+
 pub fn generate_yaml(functions: &[FunctionInfo], fuzz_target_map: &HashMap<String, FunctionInfo>) -> io::Result<()> {
     // Generate YAML per fuzzing harness
     for (harness, fuzz_target_info) in fuzz_target_map {
@@ -72,3 +112,45 @@ pub fn generate_yaml(functions: &[FunctionInfo], fuzz_target_map: &HashMap<Strin
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyse::analyse_directory;
+
+    use std::fs;
+
+    // Analyse a project made of a single library source file
+    fn analyse_source(name: &str, source: &str) -> Vec<FunctionInfo> {
+        let dir = std::env::temp_dir().join(format!("rust_function_analyser-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("lib.rs"), source).unwrap();
+        analyse_directory(dir.to_str().unwrap(), &[]).unwrap()
+    }
+
+    #[test]
+    fn function_index_locates_each_function_once() {
+        let functions = analyse_source("function_index", "pub struct Parser;
+impl Parser {
+    pub fn run(&self) {
+        helper();
+    }
+}
+
+pub fn helper() {}
+");
+        let index = build_function_index(&functions);
+        assert_eq!(index.keys().collect::<Vec<_>>(), vec!["Parser::run", "helper"]);
+        assert!(index["Parser::run"].location.ends_with("lib.rs:3:5"));
+        assert!(index["helper"].location.ends_with("lib.rs:8:8"));
+
+        let mut duplicated = functions.clone();
+        duplicated[0].start_line = 100;
+        duplicated.splice(0..0, functions.iter().cloned());
+        assert_eq!(build_function_index(&duplicated), index);
+
+        let json = serde_json::to_value(&index).unwrap();
+        assert_eq!(json["helper"]["CyclomaticComplexity"], index["helper"].complexity);
+    }
+}
@@ -13,9 +13,7 @@
  * limitations under the License.
  */
 
-mod analyse;
-mod generate_yaml;
-mod call_tree;
+use rust_function_analyser::{analyse, call_tree, config::CallTreeConfig, generate_yaml};
 
 use std::io;
 
@@ -48,13 +46,27 @@ fn main() -> io::Result<()> {
     };
 
     // Get the analysis result
-    let functions = analyse::analyse_directory(&target_directory, &exclude_dirs)?;
+    let mut functions = analyse::analyse_directory(&target_directory, &exclude_dirs)?;
+
+    // Disambiguate functions sharing a name when $RUST_ANALYSER_DEDUP_FUNCTIONS is set
+    if std::env::var_os("RUST_ANALYSER_DEDUP_FUNCTIONS").is_some() {
+        functions = analyse::canonicalize_functions(functions);
+    }
 
     // Generate call trees for fuzzing harnesses and get their paths
-    let fuzz_target_map = call_tree::generate_call_trees(&target_directory, &functions)?;
+    let mut config = CallTreeConfig::default();
+
+    // Load custom harness entry macros from the config file given in $RUST_ANALYSER_CONFIG
+    if let Ok(config_path) = std::env::var("RUST_ANALYSER_CONFIG") {
+        config.load_entry_macros(&config_path)?;
+    }
+    let fuzz_target_map = call_tree::generate_call_trees(&target_directory, &functions, &config)?;
 
     // Generate YAML using the function list and fuzz target map
     generate_yaml::generate_yaml(&functions, &fuzz_target_map)?;
 
+    // Generate the index of function definition locations
+    generate_yaml::generate_function_index(&functions)?;
+
     Ok(())
 }
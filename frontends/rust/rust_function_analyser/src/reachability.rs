@@ -0,0 +1,514 @@
+/* Copyright 2024 Fuzz Introspector Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+
+use crate::analyse::FunctionInfo;
+use crate::call_tree::find_function;
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+// Resolve the callsite destinations of a function the same way the call tree builder does.
+// Destinations that cannot be resolved to a project function are kept with their raw name.
+fn resolve_callees(
+    function_info: &FunctionInfo,
+    function_map: &HashMap<String, &FunctionInfo>,
+) -> Vec<String> {
+    let mut callees = Vec::new();
+    for callsite in &function_info.callsites {
+        let callee = match find_function(&callsite.dst, function_map) {
+            Some(func) => func.name.clone(),
+            None => callsite.dst.clone(),
+        };
+        if !callees.contains(&callee) {
+            callees.push(callee);
+        }
+    }
+    callees
+}
+
+// Base struct for the Tarjan strongly connected component search over the call graph
+struct SccSearch<'a> {
+    graph: &'a HashMap<String, Vec<String>>,
+    index: usize,
+    indices: HashMap<String, usize>,
+    low_links: HashMap<String, usize>,
+    stack: Vec<String>,
+    on_stack: HashSet<String>,
+    components: Vec<Vec<String>>,
+}
+
+impl SccSearch<'_> {
+    fn connect(&mut self, node: &str) {
+        self.indices.insert(node.to_string(), self.index);
+        self.low_links.insert(node.to_string(), self.index);
+        self.index += 1;
+        self.stack.push(node.to_string());
+        self.on_stack.insert(node.to_string());
+
+        let graph = self.graph;
+        for callee in graph.get(node).into_iter().flatten() {
+            if !graph.contains_key(callee) {
+                continue;
+            }
+            if !self.indices.contains_key(callee) {
+                self.connect(callee);
+                let low = self.low_links[node].min(self.low_links[callee]);
+                self.low_links.insert(node.to_string(), low);
+            } else if self.on_stack.contains(callee) {
+                let low = self.low_links[node].min(self.indices[callee]);
+                self.low_links.insert(node.to_string(), low);
+            }
+        }
+
+        // Pop a complete component once the root of it is reached
+        if self.low_links[node] == self.indices[node] {
+            let mut component = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack.remove(&member);
+                let is_root = member == node;
+                component.push(member);
+                if is_root {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
+
+// Build the direct callee graph of the given functions
+fn build_call_graph(functions: &[FunctionInfo]) -> HashMap<String, Vec<String>> {
+    let function_map: HashMap<String, &FunctionInfo> = functions.iter().map(|f| (f.name.clone(), f)).collect();
+    functions
+        .iter()
+        .map(|f| (f.name.clone(), resolve_callees(f, &function_map)))
+        .collect()
+}
+
+// Collapse the call graph into strongly connected components, which are returned in reverse
+// topological order so that the components of callees always come first
+fn strongly_connected_components(graph: &HashMap<String, Vec<String>>) -> Vec<Vec<String>> {
+    let mut search = SccSearch {
+        graph,
+        index: 0,
+        indices: HashMap::new(),
+        low_links: HashMap::new(),
+        stack: Vec::new(),
+        on_stack: HashSet::new(),
+        components: Vec::new(),
+    };
+    let mut names: Vec<&String> = graph.keys().collect();
+    names.sort();
+    for name in names {
+        if !search.indices.contains_key(name) {
+            search.connect(name);
+        }
+    }
+    search.components
+}
+
+// Compute the transitive closure of reachable functions for every function in the given list.
+// Cycles are collapsed into strongly connected components first, and components are processed
+// callees first so that every component reuses the memoized reach set of its callees.
+pub fn compute_transitive_reachability(functions: &[FunctionInfo]) -> HashMap<String, Vec<String>> {
+    let graph = build_call_graph(functions);
+    let components = strongly_connected_components(&graph);
+
+    // Tarjan emits components in reverse topological order, so callees are always resolved first
+    let mut reach_map: HashMap<String, HashSet<String>> = HashMap::new();
+    for component in &components {
+        let members: HashSet<&String> = component.iter().collect();
+        let mut reached = HashSet::new();
+        for member in component {
+            for callee in &graph[member] {
+                reached.insert(callee.clone());
+                if !members.contains(callee) {
+                    if let Some(callee_reach) = reach_map.get(callee) {
+                        reached.extend(callee_reach.iter().cloned());
+                    }
+                }
+            }
+        }
+        for member in component {
+            reach_map.insert(member.clone(), reached.clone());
+        }
+    }
+
+    reach_map
+        .into_iter()
+        .map(|(name, reached)| {
+            let mut reached: Vec<String> = reached.into_iter().collect();
+            reached.sort();
+            (name, reached)
+        })
+        .collect()
+}
+
+// Estimate an upper bound of the call tree depth below and including each function. A path
+// in the call tree never visits a function twice, so it passes through every component of
+// the condensed call graph at most once and stays within the component sizes along the path.
+pub fn estimate_call_depths(functions: &[FunctionInfo]) -> HashMap<String, usize> {
+    let graph = build_call_graph(functions);
+    let components = strongly_connected_components(&graph);
+
+    let mut depths: HashMap<String, usize> = HashMap::new();
+    for component in &components {
+        let members: HashSet<&String> = component.iter().collect();
+        let callee_depth = component
+            .iter()
+            .flat_map(|member| &graph[member])
+            .filter(|callee| !members.contains(callee))
+            .map(|callee| depths.get(callee).copied().unwrap_or(1))
+            .max()
+            .unwrap_or(0);
+        for member in component {
+            depths.insert(member.clone(), component.len() + callee_depth);
+        }
+    }
+    depths
+}
+
+// Replace the direct callees in the reachable list of each function with the full transitive
+// set of reachable functions
+pub fn include_transitive_reachability(functions: &mut [FunctionInfo]) {
+    let reach_map = compute_transitive_reachability(functions);
+    for function in functions.iter_mut() {
+        if let Some(reached) = reach_map.get(&function.name) {
+            function.called_functions = reached.clone();
+        }
+    }
+}
+
+// Invert the reach sets of the harnesses into the sorted list of harnesses reaching each
+// function, e.g. to tell which harnesses exercise a crashing function. Harnesses are given as
+// returned by generate_call_trees, keyed by their file path.
+pub fn reverse_reachability(
+    functions: &[FunctionInfo],
+    harnesses: &HashMap<String, FunctionInfo>,
+) -> HashMap<String, Vec<String>> {
+    let function_map: HashMap<String, &FunctionInfo> = functions.iter().map(|f| (f.name.clone(), f)).collect();
+    let reach_map = compute_transitive_reachability(functions);
+
+    let mut reverse_map: HashMap<String, Vec<String>> = HashMap::new();
+    for (harness_file, harness) in harnesses {
+        let mut reached = HashSet::new();
+        for callee in resolve_callees(harness, &function_map) {
+            if let Some(callee_reach) = reach_map.get(&callee) {
+                reached.extend(callee_reach.iter().cloned());
+            }
+            reached.insert(callee);
+        }
+        for name in reached {
+            reverse_map.entry(name).or_default().push(harness_file.clone());
+        }
+    }
+
+    for harness_files in reverse_map.values_mut() {
+        harness_files.sort();
+    }
+    reverse_map
+}
+
+// List the frontier functions of a harness, i.e. the project functions it reaches which call
+// no further project function themselves. These are often the parsers and decoders doing the
+// actual work. The names are sorted.
+pub fn leaf_functions(functions: &[FunctionInfo], harness: &FunctionInfo) -> Vec<String> {
+    let function_map: HashMap<String, &FunctionInfo> = functions.iter().map(|f| (f.name.clone(), f)).collect();
+    let graph = build_call_graph(functions);
+
+    let mut reached: HashSet<String> = HashSet::new();
+    let mut pending: Vec<String> = resolve_callees(harness, &function_map)
+        .into_iter()
+        .filter(|callee| graph.contains_key(callee))
+        .collect();
+    while let Some(name) = pending.pop() {
+        if !reached.insert(name.clone()) {
+            continue;
+        }
+        for callee in &graph[&name] {
+            if graph.contains_key(callee) && !reached.contains(callee) {
+                pending.push(callee.clone());
+            }
+        }
+    }
+
+    let mut leaves: Vec<String> = reached
+        .into_iter()
+        .filter(|name| !graph[name].iter().any(|callee| graph.contains_key(callee)))
+        .collect();
+    leaves.sort();
+    leaves
+}
+
+// Compact artifact of the functions reached by every harness for fast joins against the
+// function list. Functions are indexed by their position in the sorted list of function
+// names. The bitset of a harness is serialised as a hex string of bytes, where the bit of
+// function i is bit i % 8 of byte i / 8, so joins become bitwise operations.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReachabilityBitsets {
+    pub functions: Vec<String>,
+    pub harnesses: BTreeMap<String, String>,
+}
+
+impl ReachabilityBitsets {
+    // Build the bitsets of the harnesses as returned by generate_call_trees, keyed by their
+    // file path. A harness reaches its direct callees and everything they reach in turn.
+    pub fn build(functions: &[FunctionInfo], harnesses: &HashMap<String, FunctionInfo>) -> Self {
+        let mut names: Vec<String> = functions.iter().map(|f| f.name.clone()).collect();
+        names.sort();
+        names.dedup();
+        let index: HashMap<&String, usize> = names.iter().enumerate().map(|(i, name)| (name, i)).collect();
+
+        let function_map: HashMap<String, &FunctionInfo> = functions.iter().map(|f| (f.name.clone(), f)).collect();
+        let reach_map = compute_transitive_reachability(functions);
+        let mut bitsets = BTreeMap::new();
+        for (harness_file, harness) in harnesses {
+            let mut bytes = vec![0u8; names.len().div_ceil(8)];
+            for callee in resolve_callees(harness, &function_map) {
+                let reached = reach_map.get(&callee).into_iter().flatten().chain([&callee]);
+                for position in reached.filter_map(|name| index.get(name)) {
+                    bytes[position / 8] |= 1 << (position % 8);
+                }
+            }
+            let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+            bitsets.insert(harness_file.clone(), hex);
+        }
+
+        ReachabilityBitsets { functions: names, harnesses: bitsets }
+    }
+
+    // Decode the bitset bytes of a harness
+    pub fn bitset(&self, harness_file: &str) -> Option<Vec<u8>> {
+        let hex = self.harnesses.get(harness_file)?;
+        (0..hex.len())
+            .step_by(2)
+            .map(|start| u8::from_str_radix(hex.get(start..start + 2)?, 16).ok())
+            .collect()
+    }
+
+    // Check if a harness reaches the function of the given name
+    pub fn reaches(&self, harness_file: &str, function: &str) -> bool {
+        let position = match self.functions.binary_search_by(|name| name.as_str().cmp(function)) {
+            Ok(position) => position,
+            Err(_) => return false,
+        };
+        // Only decode the byte holding the bit of the function
+        let start = 2 * (position / 8);
+        self.harnesses
+            .get(harness_file)
+            .and_then(|hex| hex.get(start..start + 2))
+            .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+            .is_some_and(|byte| byte & (1 << (position % 8)) != 0)
+    }
+}
+
+// Enumerate up to k distinct call paths from the harness to the target function, shortest
+// first. Each path lists the function names from the harness entry to the target, and no
+// function appears twice in a path so cycles are never followed.
+pub fn reach_paths(
+    functions: &[FunctionInfo],
+    harness: &FunctionInfo,
+    target: &str,
+    k: usize,
+) -> Vec<Vec<String>> {
+    let function_map: HashMap<String, &FunctionInfo> = functions.iter().map(|f| (f.name.clone(), f)).collect();
+    let graph = build_call_graph(functions);
+    let harness_callees = resolve_callees(harness, &function_map);
+    let target = match find_function(target, &function_map) {
+        Some(func) => func.name.clone(),
+        None => target.to_string(),
+    };
+
+    // Only functions which can reach the target are worth extending a path with
+    let mut callers: HashMap<&String, Vec<&String>> = HashMap::new();
+    for (caller, callees) in &graph {
+        for callee in callees {
+            callers.entry(callee).or_default().push(caller);
+        }
+    }
+    let mut reaching: HashSet<&String> = HashSet::from([&target]);
+    let mut pending = vec![&target];
+    while let Some(name) = pending.pop() {
+        for caller in callers.get(name).into_iter().flatten() {
+            if reaching.insert(caller) {
+                pending.push(caller);
+            }
+        }
+    }
+
+    // Breadth first search over simple paths yields the shortest paths first
+    let mut paths = Vec::new();
+    let mut queue: VecDeque<Vec<&String>> = harness_callees
+        .iter()
+        .filter(|callee| reaching.contains(callee))
+        .map(|callee| vec![callee])
+        .collect();
+    while let Some(path) = queue.pop_front() {
+        if paths.len() >= k {
+            break;
+        }
+
+        let last = path[path.len() - 1];
+        if *last == target {
+            let mut names = vec![harness.name.clone()];
+            names.extend(path.iter().map(|name| name.to_string()));
+            paths.push(names);
+            continue;
+        }
+
+        for callee in graph.get(last).into_iter().flatten() {
+            if reaching.contains(callee) && !path.contains(&callee) {
+                let mut extended = path.clone();
+                extended.push(callee);
+                queue.push_back(extended);
+            }
+        }
+    }
+
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyse::analyse_directory;
+
+    use std::fs;
+
+    // Analyse a project made of a single library source file
+    fn analyse_source(name: &str, source: &str) -> Vec<FunctionInfo> {
+        let dir = std::env::temp_dir().join(format!("rust_function_analyser-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("lib.rs"), source).unwrap();
+        analyse_directory(dir.to_str().unwrap(), &[]).unwrap()
+    }
+
+    const CYCLIC_LIB: &str = "pub fn a() { b(); }
+pub fn b() { c(); }
+pub fn c() { b(); }
+pub fn d() {}
+";
+
+    #[test]
+    fn transitive_reachability_follows_cycles() {
+        let functions = analyse_source("transitive_reachability", CYCLIC_LIB);
+        let reach_map = compute_transitive_reachability(&functions);
+        assert_eq!(reach_map["a"], vec!["b", "c"]);
+        assert_eq!(reach_map["b"], vec!["b", "c"]);
+        assert_eq!(reach_map["c"], vec!["b", "c"]);
+        assert!(reach_map["d"].is_empty());
+
+        let mut functions = functions;
+        include_transitive_reachability(&mut functions);
+        let a = functions.iter().find(|function| function.name == "a").unwrap();
+        assert_eq!(a.called_functions, vec!["b", "c"]);
+    }
+
+    #[test]
+    fn reach_paths_lists_the_shortest_paths_first() {
+        let functions = analyse_source("reach_paths", "pub fn harness() { a(); x(); d(); }
+pub fn a() { b(); c(); }
+pub fn b() { c(); a(); }
+pub fn c() {}
+pub fn x() { c(); }
+pub fn d() {}
+");
+        let harness = functions.iter().find(|function| function.name == "harness").unwrap();
+        let paths = reach_paths(&functions, harness, "c", 10);
+        assert_eq!(paths, vec![
+            vec!["harness", "a", "c"],
+            vec!["harness", "x", "c"],
+            vec!["harness", "a", "b", "c"],
+        ]);
+        assert_eq!(reach_paths(&functions, harness, "c", 1).len(), 1);
+        assert_eq!(reach_paths(&functions, harness, "d", 10), vec![vec!["harness", "d"]]);
+        assert!(reach_paths(&functions, harness, "missing", 10).is_empty());
+    }
+
+    #[test]
+    fn call_depths_count_cycles_once() {
+        let functions = analyse_source("call_depths", CYCLIC_LIB);
+        let depths = estimate_call_depths(&functions);
+        assert_eq!(depths["a"], 3);
+        assert_eq!(depths["b"], 2);
+        assert_eq!(depths["c"], 2);
+        assert_eq!(depths["d"], 1);
+    }
+
+    #[test]
+    fn reverse_reachability_lists_the_reaching_harnesses() {
+        let functions = analyse_source("reverse_reachability", "pub fn a() { b(); }
+pub fn b() { c(); }
+pub fn c() {}
+pub fn d() { c(); }
+");
+        let harness = |name: &str| functions.iter().find(|function| function.name == name).unwrap().clone();
+        let harnesses = HashMap::from([
+            ("fuzz/second.rs".to_string(), harness("d")),
+            ("fuzz/first.rs".to_string(), harness("a")),
+        ]);
+        let reverse_map = reverse_reachability(&functions, &harnesses);
+        assert_eq!(reverse_map["b"], vec!["fuzz/first.rs"]);
+        assert_eq!(reverse_map["c"], vec!["fuzz/first.rs", "fuzz/second.rs"]);
+        assert!(!reverse_map.contains_key("a"));
+        assert!(!reverse_map.contains_key("d"));
+    }
+
+    #[test]
+    fn leaf_functions_are_the_reached_frontier() {
+        let functions = analyse_source("leaf_functions", "pub fn harness() { a(); d(); }
+pub fn a() { b(); e(); }
+pub fn b() { c(); std::mem::drop(1); }
+pub fn c() { b(); }
+pub fn d() {}
+pub fn e() {}
+pub fn unreached() {}
+");
+        let harness = functions.iter().find(|function| function.name == "harness").unwrap();
+        assert_eq!(leaf_functions(&functions, harness), vec!["d", "e"]);
+    }
+
+    #[test]
+    fn reachability_bitsets_mark_the_reached_functions() {
+        let functions = analyse_source("reachability_bitsets", CYCLIC_LIB);
+        let harness = |name: &str| functions.iter().find(|function| function.name == name).unwrap().clone();
+        let harnesses = HashMap::from([
+            ("fuzz/first.rs".to_string(), harness("a")),
+            ("fuzz/second.rs".to_string(), harness("d")),
+        ]);
+        let bitsets = ReachabilityBitsets::build(&functions, &harnesses);
+        assert_eq!(bitsets.functions, vec!["a", "b", "c", "d"]);
+        assert_eq!(bitsets.harnesses["fuzz/first.rs"], "06");
+        assert_eq!(bitsets.harnesses["fuzz/second.rs"], "00");
+        assert_eq!(bitsets.bitset("fuzz/first.rs"), Some(vec![0x06]));
+        assert!(bitsets.reaches("fuzz/first.rs", "c"));
+        assert!(!bitsets.reaches("fuzz/first.rs", "d"));
+        assert!(!bitsets.reaches("fuzz/missing.rs", "c"));
+
+        // Only the byte holding the bit of the function is decoded
+        let bitsets = ReachabilityBitsets {
+            functions: (0..10).map(|index| format!("f{}", index)).collect(),
+            harnesses: BTreeMap::from([("fuzz/first.rs".to_string(), "zz02".to_string())]),
+        };
+        assert!(bitsets.reaches("fuzz/first.rs", "f9"));
+        assert!(!bitsets.reaches("fuzz/first.rs", "f8"));
+        assert!(!bitsets.reaches("fuzz/first.rs", "f0"));
+
+        let json = serde_json::to_string(&bitsets).unwrap();
+        assert_eq!(serde_json::from_str::<ReachabilityBitsets>(&json).unwrap(), bitsets);
+    }
+}
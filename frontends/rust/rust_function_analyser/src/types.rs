@@ -0,0 +1,316 @@
+/* Copyright 2024 Fuzz Introspector Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+
+use quote::ToTokens;
+use syn::{GenericArgument, PathArguments, Type, TypeParamBound};
+
+// Wrapper types where the `?` operator produces the first generic argument
+const TRY_TYPES: &[&str] = &["Result", "Option"];
+
+// Collection types where indexing produces the first generic argument
+const SEQUENCE_TYPES: &[&str] = &["Vec", "VecDeque"];
+
+// Collection types where indexing produces the second generic argument
+const MAP_TYPES: &[&str] = &["HashMap", "BTreeMap"];
+
+// Standard iterator types yielding their first generic argument, e.g. `vec::IntoIter<Item>`
+const ITERATOR_TYPES: &[&str] = &["Iter", "IterMut", "IntoIter", "Drain"];
+
+// Smart pointer types dereferencing to their first generic argument for method calls,
+// including the lazily initialised values commonly used for statics and the borrow and lock
+// guards returned by getters of shared state
+const SMART_POINTER_TYPES: &[&str] = &[
+    "Box",
+    "Rc",
+    "Arc",
+    "LazyLock",
+    "LazyCell",
+    "Lazy",
+    "Ref",
+    "RefMut",
+    "MutexGuard",
+    "RwLockReadGuard",
+    "RwLockWriteGuard",
+];
+
+// Parse a type recorded as a string, either from the harness source or from the full
+// return type of a FunctionInfo
+pub(crate) fn parse_type(ty: &str) -> Option<Type> {
+    syn::parse_str::<Type>(ty).ok()
+}
+
+// Render a type in the token string format used for all recorded types
+pub(crate) fn type_to_string(ty: &Type) -> String {
+    ty.to_token_stream().to_string()
+}
+
+// Remove any references and parentheses around a type
+fn strip_references(ty: &Type) -> &Type {
+    match ty {
+        Type::Reference(reference) => strip_references(&reference.elem),
+        Type::Paren(paren) => strip_references(&paren.elem),
+        Type::Group(group) => strip_references(&group.elem),
+        _ => ty,
+    }
+}
+
+// Retrieve the name of the first trait bound, e.g. `Decode` for `impl Decode + Send`
+fn first_trait_name<'a>(bounds: impl IntoIterator<Item = &'a TypeParamBound>) -> Option<String> {
+    bounds.into_iter().find_map(|bound| match bound {
+        TypeParamBound::Trait(trait_bound) => trait_bound.path.segments.last().map(|seg| seg.ident.to_string()),
+        _ => None,
+    })
+}
+
+// Retrieve the type name used to qualify method calls, e.g. `Parser` for `&crate::Parser<T>`.
+// Opaque `impl Trait` and `dyn Trait` types are named by their trait, so that methods called
+// on them are qualified with the trait, e.g. `Decode::decode`.
+pub(crate) fn type_name(ty: &str) -> String {
+    match parse_type(ty) {
+        Some(parsed) => match strip_references(&parsed) {
+            Type::Path(type_path) => type_path
+                .path
+                .segments
+                .last()
+                .map(|seg| seg.ident.to_string())
+                .unwrap_or_default(),
+            Type::ImplTrait(impl_trait) => first_trait_name(&impl_trait.bounds).unwrap_or_default(),
+            Type::TraitObject(trait_object) => first_trait_name(&trait_object.bounds).unwrap_or_default(),
+            other => type_to_string(other).replace(' ', ""),
+        },
+        None => ty.replace(' ', ""),
+    }
+}
+
+// Retrieve the generic type arguments of a path type, e.g. `T` and `E` for `&Result<T, E>`
+pub(crate) fn generic_arguments(ty: &str) -> Vec<String> {
+    let parsed = match parse_type(ty) {
+        Some(parsed) => parsed,
+        None => return Vec::new(),
+    };
+    let segment = match strip_references(&parsed) {
+        Type::Path(type_path) => match type_path.path.segments.last() {
+            Some(segment) => segment.clone(),
+            None => return Vec::new(),
+        },
+        _ => return Vec::new(),
+    };
+
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) => arguments
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(type_to_string(ty)),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// Determine the type produced by applying the `?` operator to a value of the given type
+pub(crate) fn unwrap_try(ty: &str) -> String {
+    if TRY_TYPES.contains(&type_name(ty).as_str()) {
+        if let Some(inner) = generic_arguments(ty).into_iter().next() {
+            return inner;
+        }
+    }
+    ty.to_string()
+}
+
+// Determine the element type produced by indexing a value of the given type
+pub(crate) fn index_element_type(ty: &str) -> Option<String> {
+    match strip_references(&parse_type(ty)?) {
+        Type::Array(array) => return Some(type_to_string(&array.elem)),
+        Type::Slice(slice) => return Some(type_to_string(&slice.elem)),
+        _ => {}
+    }
+
+    let name = type_name(ty);
+    let arguments = generic_arguments(ty);
+    if SEQUENCE_TYPES.contains(&name.as_str()) {
+        arguments.into_iter().next()
+    } else if MAP_TYPES.contains(&name.as_str()) {
+        arguments.into_iter().nth(1)
+    } else {
+        None
+    }
+}
+
+// Determine the item type produced by iterating over a value of the given type, either an
+// iterator such as `impl Iterator<Item = T>` or a collection looped over directly. Maps
+// yield their entries as a tuple of the key and value types. A borrowed collection yields
+// references, e.g. `& Parser` for `& [Parser]`.
+pub(crate) fn iterator_item_type(ty: &str) -> Option<String> {
+    let parsed = parse_type(ty)?;
+    if let Type::ImplTrait(impl_trait) = strip_references(&parsed) {
+        return impl_trait.bounds.iter().find_map(|bound| match bound {
+            TypeParamBound::Trait(trait_bound) => {
+                let segment = trait_bound.path.segments.last()?;
+                match &segment.arguments {
+                    PathArguments::AngleBracketed(arguments) if segment.ident == "Iterator" => {
+                        arguments.args.iter().find_map(|arg| match arg {
+                            GenericArgument::AssocType(assoc) if assoc.ident == "Item" => Some(type_to_string(&assoc.ty)),
+                            _ => None,
+                        })
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        });
+    }
+
+    let name = type_name(ty);
+    let arguments = generic_arguments(ty);
+    if ITERATOR_TYPES.contains(&name.as_str()) {
+        return arguments.into_iter().next();
+    }
+
+    let borrow = match &parsed {
+        Type::Reference(reference) if reference.mutability.is_some() => "& mut ",
+        Type::Reference(_) => "& ",
+        _ => "",
+    };
+    if MAP_TYPES.contains(&name.as_str()) && arguments.len() == 2 {
+        let key_borrow = if borrow.is_empty() { "" } else { "& " };
+        Some(format!("( {}{} , {}{} )", key_borrow, arguments[0], borrow, arguments[1]))
+    } else {
+        index_element_type(ty).map(|element| format!("{}{}", borrow, element))
+    }
+}
+
+// Check if a type is an iterator rather than a collection, e.g. `impl Iterator<Item = T>`
+pub(crate) fn is_iterator_type(ty: &str) -> bool {
+    let name = type_name(ty);
+    name == "Iterator" || ITERATOR_TYPES.contains(&name.as_str())
+}
+
+// Determine the element type at the given position of a tuple type, e.g. for `.0`
+pub(crate) fn tuple_element_type(ty: &str, index: usize) -> Option<String> {
+    match strip_references(&parse_type(ty)?) {
+        Type::Tuple(tuple) => tuple.elems.iter().nth(index).map(type_to_string),
+        _ => None,
+    }
+}
+
+// Remove the smart pointers around a type whose methods are reached through Deref, e.g.
+// `Parser` for `Rc<Parser>` or `Mutex<Parser>` for `Arc<Mutex<Parser>>`
+pub(crate) fn deref_smart_pointers(ty: &str) -> String {
+    let mut ty = ty.to_string();
+    while SMART_POINTER_TYPES.contains(&type_name(&ty).as_str()) {
+        match generic_arguments(&ty).into_iter().next() {
+            Some(inner) => ty = inner,
+            None => break,
+        }
+    }
+    ty
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn type_name_strips_references_and_paths() {
+        assert_eq!(type_name("& crate :: Parser < T >"), "Parser");
+        assert_eq!(type_name("& mut Parser"), "Parser");
+        assert_eq!(generic_arguments("& Result < Parser , Error >"), vec!["Parser", "Error"]);
+        assert!(generic_arguments("Parser").is_empty());
+    }
+
+    #[test]
+    fn unwrap_try_unwraps_results_and_options() {
+        assert_eq!(unwrap_try("Result < Parser , Error >"), "Parser");
+        assert_eq!(unwrap_try("Option < Parser >"), "Parser");
+        assert_eq!(unwrap_try("Parser"), "Parser");
+    }
+
+    #[test]
+    fn index_element_type_of_sequences_maps_and_slices() {
+        assert_eq!(index_element_type("Vec < Parser >").as_deref(), Some("Parser"));
+        assert_eq!(index_element_type("& VecDeque < Parser >").as_deref(), Some("Parser"));
+        assert_eq!(index_element_type("HashMap < String , Parser >").as_deref(), Some("Parser"));
+        assert_eq!(index_element_type("[Parser ; 4]").as_deref(), Some("Parser"));
+        assert_eq!(index_element_type("& [u8]").as_deref(), Some("u8"));
+        assert_eq!(index_element_type("Parser"), None);
+    }
+
+    #[test]
+    fn tuple_element_type_by_index() {
+        assert_eq!(tuple_element_type("& (u8 , Parser)", 1).as_deref(), Some("Parser"));
+        assert_eq!(tuple_element_type("(u8 , Parser)", 2), None);
+        assert_eq!(tuple_element_type("Parser", 0), None);
+    }
+
+    #[test]
+    fn deref_smart_pointers_stops_at_the_wrapped_type() {
+        assert_eq!(deref_smart_pointers("Rc < Parser >"), "Parser");
+        assert_eq!(deref_smart_pointers("Arc < Mutex < Parser > >"), "Mutex < Parser >");
+        assert_eq!(deref_smart_pointers("Box < Box < Parser > >"), "Parser");
+        assert_eq!(deref_smart_pointers("Vec < Parser >"), "Vec < Parser >");
+    }
+
+    #[test]
+    fn type_name_of_opaque_types_is_the_trait() {
+        assert_eq!(type_name("impl Decode + Send"), "Decode");
+        assert_eq!(type_name("& dyn crate :: Decode"), "Decode");
+        assert_eq!(type_name("Box < dyn Decode >"), "Box");
+    }
+
+    #[test]
+    fn lazily_initialised_values_deref_to_their_type() {
+        assert_eq!(deref_smart_pointers("LazyLock < Parser >"), "Parser");
+        assert_eq!(deref_smart_pointers("once_cell :: sync :: Lazy < Arc < Parser > >"), "Parser");
+    }
+
+    #[test]
+    fn guards_deref_to_the_guarded_type() {
+        assert_eq!(deref_smart_pointers("MutexGuard < '_ , Parser >"), "Parser");
+        assert_eq!(deref_smart_pointers("std :: cell :: RefMut < 'a , Parser >"), "Parser");
+        assert_eq!(deref_smart_pointers("RwLockReadGuard < Box < Parser > >"), "Parser");
+    }
+
+    #[test]
+    fn iterator_item_type_of_iterators_and_collections() {
+        assert_eq!(iterator_item_type("impl Iterator < Item = Parser >").as_deref(), Some("Parser"));
+        assert_eq!(iterator_item_type("std :: vec :: IntoIter < Parser >").as_deref(), Some("Parser"));
+        assert_eq!(iterator_item_type("Vec < Parser >").as_deref(), Some("Parser"));
+        assert_eq!(
+            iterator_item_type("HashMap < String , Parser >").as_deref(),
+            Some("( String , Parser )")
+        );
+        assert_eq!(iterator_item_type("Parser"), None);
+    }
+
+    #[test]
+    fn iterator_item_type_keeps_the_borrow_of_collections() {
+        assert_eq!(iterator_item_type("& [Parser]").as_deref(), Some("& Parser"));
+        assert_eq!(iterator_item_type("& mut Vec < Parser >").as_deref(), Some("& mut Parser"));
+        assert_eq!(
+            iterator_item_type("& mut HashMap < String , Parser >").as_deref(),
+            Some("( & String , & mut Parser )")
+        );
+    }
+
+    #[test]
+    fn iterators_are_told_apart_from_collections() {
+        assert!(is_iterator_type("impl Iterator < Item = Parser >"));
+        assert!(is_iterator_type("std :: slice :: Iter < Parser >"));
+        assert!(!is_iterator_type("Vec < Parser >"));
+    }
+}